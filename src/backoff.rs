@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+/// Exponentially growing delay between retries of a failing operation.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+    /// Fraction of each delay that is randomized, so retries of many
+    /// clients do not line up
+    jitter: f64,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+            jitter: 0.0,
+        }
+    }
+
+    /// Shorten each delay by a random amount of up to `fraction` of it.
+    pub fn with_jitter(mut self, fraction: f64) -> Self {
+        self.jitter = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Delay to wait before the next attempt; doubles on every call up to `max`.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay.mul_f64(1.0 - self.jitter * fastrand::f64())
+    }
+
+    /// Start over from the initial delay after a success.
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_jitter() {
+        let mut backoff = Backoff::new(Duration::from_secs(8), Duration::from_secs(60)).with_jitter(0.5);
+        for base in [8, 16, 32, 60, 60] {
+            let delay = backoff.next_delay();
+            assert!(delay <= Duration::from_secs(base), "{:?}", delay);
+            assert!(delay >= Duration::from_secs(base) / 2, "{:?}", delay);
+        }
+    }
+}
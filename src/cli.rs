@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use crate::config::{RepositoryConfig, Visibility};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -14,9 +15,9 @@ pub enum Commands {
     /// Start monitoring a directory
     #[command(arg_required_else_help = true)]
     Watch {
-        /// Directory to monitor (defaults to current directory)
+        /// Registered project name or directory to monitor (defaults to current directory)
         #[arg(default_value = ".")]
-        path: PathBuf,
+        project: String,
 
         /// Enable verbose output
         #[arg(short, long)]
@@ -30,15 +31,68 @@ pub enum Commands {
         #[arg(default_value = ".")]
         path: PathBuf,
 
-        /// Project name (defaults to directory name)
+        /// Project name, used for the remote repository, README title and registry
+        /// (defaults to directory name)
         #[arg(short, long)]
         name: Option<String>,
 
+        /// What to do when the remote repository already exists
+        #[arg(long, value_enum, default_value_t = crate::git::OnConflict::Reuse)]
+        on_conflict: crate::git::OnConflict,
+
+        /// Template to scaffold the project from: a name from `pms templates list`
+        /// or a template directory
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// How git reaches the remote (defaults to hosting.transport)
+        #[arg(long, value_enum)]
+        transport: Option<crate::config::Transport>,
+
+        /// Create a deploy key for this project and register it with the provider
+        /// (requires --transport ssh)
+        #[arg(long)]
+        deploy_key: bool,
+
+        #[command(flatten)]
+        repository: RepositoryArgs,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Watch every registered project in the background
+    Daemon {
         /// Enable verbose output
         #[arg(short, long)]
         verbose: bool,
     },
 
+    /// Show the state of every project the running pms watches
+    Status,
+
+    /// Stop syncing a project; changes are still recorded
+    Pause {
+        /// Project name
+        project: String,
+    },
+
+    /// Resume syncing a paused project
+    Resume {
+        /// Project name
+        project: String,
+    },
+
+    /// Sync a project immediately, even while paused
+    SyncNow {
+        /// Project name
+        project: String,
+    },
+
+    /// Stop the running pms after a final sync
+    Stop,
+
     /// Configure PMS settings
     Config {
         /// Set GitHub token
@@ -53,4 +107,230 @@ pub enum Commands {
         #[arg(long)]
         email: Option<String>,
     },
-} 
\ No newline at end of file
+
+    /// Manage the registry of projects pms knows about
+    Projects {
+        #[command(subcommand)]
+        command: ProjectsCommand,
+    },
+
+    /// Manage the templates `pms init --template` scaffolds projects from
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+
+    /// Inspect and resolve versions parked after sync conflicts
+    Conflicts {
+        /// Project directory (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        #[command(subcommand)]
+        command: ConflictsCommand,
+    },
+
+    /// List the snapshots pms made, newest first
+    History {
+        /// File or directory to show snapshots for (defaults to the whole project)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Number of snapshots to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Bring a file or directory back to an earlier version as a new snapshot
+    Restore {
+        /// File or directory to restore
+        path: PathBuf,
+
+        /// Commit, or time like 2h, 3d or "2024-03-01 10:15", to take the version from
+        #[arg(long)]
+        at: String,
+    },
+
+    /// Fold the autosaves of the current branch into one commit on it
+    Squash {
+        /// Project directory (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// Commit message (defaults to a summary of the changed files)
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
+    /// Fold old snapshots together following the `retention` policy in the config
+    Compact {
+        /// Project directory (defaults to current directory)
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// Only report how many snapshots would be folded
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check git, the config and that project remotes are reachable
+    Doctor {
+        /// Registered project name or directory (defaults to every registered project)
+        project: Option<String>,
+    },
+
+    /// Git credential helper serving the configured token
+    #[command(hide = true)]
+    Credential {
+        /// Credential helper operation requested by git
+        #[arg(value_enum)]
+        operation: crate::credential::Operation,
+    },
+}
+
+/// Overrides for the `[repository]` config section when creating a repository
+#[derive(Args, Debug, Default)]
+pub struct RepositoryArgs {
+    /// Repository visibility
+    #[arg(long, value_enum)]
+    pub visibility: Option<Visibility>,
+
+    /// Create the repository in this organization or group
+    #[arg(long)]
+    pub org: Option<String>,
+
+    /// Repository description
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Repository homepage URL
+    #[arg(long)]
+    pub homepage: Option<String>,
+
+    /// Repository topic; repeat the flag or separate topics with commas
+    #[arg(long = "topic", value_delimiter = ',')]
+    pub topics: Vec<String>,
+
+    /// License template, e.g. mit or apache-2.0
+    #[arg(long)]
+    pub license: Option<String>,
+
+    /// Gitignore template, e.g. Rust or Node
+    #[arg(long)]
+    pub gitignore: Option<String>,
+
+    /// Branch a new repository starts on; existing repositories keep theirs
+    #[arg(long)]
+    pub branch: Option<String>,
+}
+
+impl RepositoryArgs {
+    pub fn apply(self, repository: &mut RepositoryConfig) {
+        if let Some(visibility) = self.visibility {
+            repository.visibility = visibility;
+        }
+        if !self.topics.is_empty() {
+            repository.topics = self.topics;
+        }
+
+        let fields = [
+            (self.org, &mut repository.organization),
+            (self.description, &mut repository.description),
+            (self.homepage, &mut repository.homepage),
+            (self.license, &mut repository.license_template),
+            (self.gitignore, &mut repository.gitignore_template),
+            (self.branch, &mut repository.branch),
+        ];
+        for (value, field) in fields {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum ProjectsCommand {
+    /// List registered projects
+    List,
+
+    /// Register an existing project directory
+    Add {
+        /// Project directory (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Project name (defaults to directory name)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// Forget a project; its files and remote repository are left alone
+    Remove {
+        /// Project name
+        name: String,
+    },
+
+    /// Show the details of a project
+    Show {
+        /// Project name
+        name: String,
+    },
+
+    /// Rename a project in the registry
+    Rename {
+        /// Current project name
+        name: String,
+
+        /// New project name
+        new_name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplatesCommand {
+    /// List built-in and added templates
+    List,
+
+    /// Add a template from a directory or git URL
+    Add {
+        /// Template name
+        name: String,
+
+        /// Template directory or git URL; a copy is stored
+        source: String,
+    },
+
+    /// Remove an added template
+    Remove {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConflictsCommand {
+    /// List parked conflict versions
+    List,
+
+    /// Show the difference between the current and a parked version
+    Show {
+        /// Conflict branch name
+        name: String,
+    },
+
+    /// Merge a parked version back, choosing a side for conflicting lines
+    #[command(group(clap::ArgGroup::new("side").required(true).args(["ours", "theirs"])))]
+    Resolve {
+        /// Conflict branch name
+        name: String,
+
+        /// Keep the parked local version for conflicting lines
+        #[arg(long)]
+        ours: bool,
+
+        /// Keep the remote version for conflicting lines
+        #[arg(long)]
+        theirs: bool,
+    },
+}
@@ -0,0 +1,233 @@
+use crate::config::CommitConfig;
+use crate::error::relative_path;
+use chrono::Local;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Renamed,
+    Deleted,
+}
+
+impl ChangeKind {
+    const ALL: [ChangeKind; 4] = [
+        ChangeKind::Added,
+        ChangeKind::Modified,
+        ChangeKind::Renamed,
+        ChangeKind::Deleted,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Renamed => "renamed",
+            ChangeKind::Deleted => "deleted",
+        }
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "Add",
+            ChangeKind::Modified => "Update",
+            ChangeKind::Renamed => "Rename",
+            ChangeKind::Deleted => "Delete",
+        }
+    }
+}
+
+/// Files the watcher saw change since the last sync, keyed by path.
+#[derive(Debug, Default, Clone)]
+pub struct ChangeSet {
+    changes: BTreeMap<PathBuf, ChangeKind>,
+}
+
+impl ChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, path: PathBuf, kind: ChangeKind) {
+        // A later modification does not hide that the file is new or renamed
+        let keep = matches!(
+            (self.changes.get(&path), kind),
+            (Some(ChangeKind::Added | ChangeKind::Renamed), ChangeKind::Modified)
+        );
+
+        if !keep {
+            self.changes.insert(path, kind);
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.changes.contains_key(path)
+    }
+
+    pub fn kind(&self, path: &Path) -> Option<ChangeKind> {
+        self.changes.get(path).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PathBuf, ChangeKind)> {
+        self.changes.iter().map(|(path, kind)| (path, *kind))
+    }
+
+    fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.values().filter(|k| **k == kind).count()
+    }
+}
+
+/// Build the commit subject and body for a set of changes below `root`.
+pub fn commit_message(changes: &ChangeSet, config: &CommitConfig, root: &Path) -> (String, Option<String>) {
+    let files: Vec<(PathBuf, ChangeKind)> = changes
+        .iter()
+        .map(|(path, kind)| (relative_path(path, root), kind))
+        .collect();
+
+    let top_dir = top_directory(&files);
+    let subject = config
+        .subject
+        .replace("{summary}", &summary(&files, top_dir.as_deref()))
+        .replace("{total}", &files.len().to_string())
+        .replace("{added}", &changes.count(ChangeKind::Added).to_string())
+        .replace("{modified}", &changes.count(ChangeKind::Modified).to_string())
+        .replace("{renamed}", &changes.count(ChangeKind::Renamed).to_string())
+        .replace("{deleted}", &changes.count(ChangeKind::Deleted).to_string())
+        .replace("{hostname}", &gethostname::gethostname().to_string_lossy())
+        .replace("{timestamp}", &Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .replace("{top_dir}", top_dir.as_deref().unwrap_or("."));
+
+    let body = if config.body && !files.is_empty() {
+        Some(body(&files))
+    } else {
+        None
+    };
+
+    (subject, body)
+}
+
+/// First directory shared by every changed path, if any
+fn top_directory(files: &[(PathBuf, ChangeKind)]) -> Option<String> {
+    let mut dirs = files.iter().map(|(path, _)| {
+        let mut components = path.components();
+        let first = components.next()?;
+        // Top-level files have no directory
+        components.next()?;
+        Some(first.as_os_str().to_string_lossy().to_string())
+    });
+
+    let first = dirs.next()??;
+    dirs.all(|dir| dir.as_deref() == Some(first.as_str())).then_some(first)
+}
+
+fn summary(files: &[(PathBuf, ChangeKind)], top_dir: Option<&str>) -> String {
+    match files {
+        [] => "Project update".to_string(),
+        [(path, kind)] => format!("{} {}", kind.verb(), path.display()),
+        _ => {
+            let counts: Vec<String> = ChangeKind::ALL
+                .iter()
+                .filter_map(|kind| {
+                    let count = files.iter().filter(|(_, k)| k == kind).count();
+                    (count > 0).then(|| format!("{} {}", count, kind.label()))
+                })
+                .collect();
+
+            match top_dir {
+                Some(dir) => format!("Update {} files in {} ({})", files.len(), dir, counts.join(", ")),
+                None => format!("Update {} files ({})", files.len(), counts.join(", ")),
+            }
+        }
+    }
+}
+
+fn body(files: &[(PathBuf, ChangeKind)]) -> String {
+    let mut sections = Vec::new();
+
+    for kind in ChangeKind::ALL {
+        let paths: Vec<String> = files
+            .iter()
+            .filter(|(_, k)| *k == kind)
+            .map(|(path, _)| format!("  {}", path.display()))
+            .collect();
+
+        if !paths.is_empty() {
+            let mut title = kind.label().to_string();
+            title[..1].make_ascii_uppercase();
+            sections.push(format!("{}:\n{}", title, paths.join("\n")));
+        }
+    }
+
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(subject: &str) -> CommitConfig {
+        CommitConfig {
+            subject: subject.to_string(),
+            body: true,
+            ..CommitConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_change_set_record() {
+        let mut changes = ChangeSet::new();
+        changes.record(PathBuf::from("a.txt"), ChangeKind::Added);
+        changes.record(PathBuf::from("a.txt"), ChangeKind::Modified);
+        changes.record(PathBuf::from("b.txt"), ChangeKind::Modified);
+        changes.record(PathBuf::from("b.txt"), ChangeKind::Deleted);
+
+        let kinds: Vec<ChangeKind> = changes.iter().map(|(_, kind)| kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Added, ChangeKind::Deleted]);
+    }
+
+    #[test]
+    fn test_commit_message() {
+        let root = Path::new("/project");
+        let mut changes = ChangeSet::new();
+
+        changes.record(root.join("src/main.rs"), ChangeKind::Modified);
+        let (subject, body) = commit_message(&changes, &config("{summary}"), root);
+        assert_eq!(subject, "Update src/main.rs");
+        assert_eq!(body.unwrap(), "Modified:\n  src/main.rs");
+
+        changes.record(root.join("src/lib.rs"), ChangeKind::Added);
+        changes.record(root.join("src/old.rs"), ChangeKind::Deleted);
+        let (subject, body) = commit_message(&changes, &config("{summary}"), root);
+        assert_eq!(subject, "Update 3 files in src (1 added, 1 modified, 1 deleted)");
+        assert_eq!(
+            body.unwrap(),
+            "Added:\n  src/lib.rs\n\nModified:\n  src/main.rs\n\nDeleted:\n  src/old.rs"
+        );
+
+        changes.record(root.join("README.md"), ChangeKind::Modified);
+        let (subject, _) = commit_message(
+            &changes,
+            &config("[{top_dir}] {total} files: +{added} ~{modified} -{deleted}"),
+            root,
+        );
+        assert_eq!(subject, "[.] 4 files: +1 ~2 -1");
+
+        let (subject, body) = commit_message(&ChangeSet::new(), &config("{summary}"), root);
+        assert_eq!(subject, "Project update");
+        assert!(body.is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::config;
+use crate::error::{PMSError, Result};
+use crate::history::Compaction;
+use crate::watcher::{WatcherCommand, WatcherHandle};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// Request sent to a running pms as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Status,
+    Pause { project: String },
+    Resume { project: String },
+    SyncNow { project: String },
+    Stop,
+}
+
+/// Reply to a [`Request`], also one line of JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<ProjectStatus>,
+}
+
+impl Response {
+    fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Self::default()
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            ok: false,
+            error: Some(message),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStatus {
+    pub name: String,
+    pub path: PathBuf,
+    /// One of `watching`, `paused`, `syncing` or `restarting`
+    pub state: String,
+    pub pending: usize,
+    #[serde(default)]
+    pub unpushed: usize,
+    pub last_sync: Option<DateTime<Local>>,
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
+struct ProjectEntry {
+    path: PathBuf,
+    paused: bool,
+    /// Missing while the watcher is starting or being restarted
+    watcher: Option<WatcherHandle>,
+}
+
+/// Routes control requests to the watchers of a running pms.
+pub struct Controller {
+    projects: Mutex<BTreeMap<String, ProjectEntry>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl Controller {
+    pub fn new(shutdown: watch::Sender<bool>) -> Self {
+        Self {
+            projects: Mutex::new(BTreeMap::new()),
+            shutdown,
+        }
+    }
+
+    pub fn add_project(&self, name: &str, path: &Path) {
+        self.projects.lock().unwrap().insert(
+            name.to_string(),
+            ProjectEntry {
+                path: path.to_path_buf(),
+                paused: false,
+                watcher: None,
+            },
+        );
+    }
+
+    /// Connect a started watcher, keeping it paused if the project was.
+    pub fn attach(&self, name: &str, watcher: WatcherHandle) {
+        let mut projects = self.projects.lock().unwrap();
+        if let Some(entry) = projects.get_mut(name) {
+            if entry.paused {
+                let _ = watcher.commands.send(WatcherCommand::Pause);
+            }
+            entry.watcher = Some(watcher);
+        }
+    }
+
+    pub fn detach(&self, name: &str) {
+        if let Some(entry) = self.projects.lock().unwrap().get_mut(name) {
+            entry.watcher = None;
+        }
+    }
+
+    pub fn is_paused(&self, name: &str) -> bool {
+        self.projects.lock().unwrap().get(name).is_some_and(|entry| entry.paused)
+    }
+
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    pub async fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Status => Response {
+                ok: true,
+                projects: self.status(),
+                ..Response::default()
+            },
+            Request::Pause { project } => self.set_paused(&project, true),
+            Request::Resume { project } => self.set_paused(&project, false),
+            Request::SyncNow { project } => self.sync_now(&project).await,
+            Request::Stop => {
+                self.stop();
+                Response::ok()
+            }
+        }
+    }
+
+    fn status(&self) -> Vec<ProjectStatus> {
+        let projects = self.projects.lock().unwrap();
+        projects
+            .iter()
+            .map(|(name, entry)| {
+                let status = entry
+                    .watcher
+                    .as_ref()
+                    .map(|watcher| watcher.status.borrow().clone())
+                    .unwrap_or_default();
+
+                let state = if entry.watcher.is_none() {
+                    "restarting"
+                } else if status.syncing {
+                    "syncing"
+                } else if entry.paused {
+                    "paused"
+                } else {
+                    "watching"
+                };
+
+                ProjectStatus {
+                    name: name.clone(),
+                    path: entry.path.clone(),
+                    state: state.to_string(),
+                    pending: status.pending,
+                    unpushed: status.unpushed,
+                    last_sync: status.last_sync,
+                    last_error: status.last_error,
+                    skipped: status.skipped,
+                }
+            })
+            .collect()
+    }
+
+    fn set_paused(&self, name: &str, paused: bool) -> Response {
+        let mut projects = self.projects.lock().unwrap();
+        let Some(entry) = projects.get_mut(name) else {
+            return Response::error(format!("Unknown project: {}", name));
+        };
+
+        entry.paused = paused;
+        if let Some(watcher) = &entry.watcher {
+            let command = if paused { WatcherCommand::Pause } else { WatcherCommand::Resume };
+            let _ = watcher.commands.send(command);
+        }
+        Response::ok()
+    }
+
+    async fn sync_now(&self, name: &str) -> Response {
+        let commands = match self.watcher_commands(name) {
+            Ok(commands) => commands,
+            Err(e) => return Response::error(e),
+        };
+
+        let (reply, result) = oneshot::channel();
+        if commands.send(WatcherCommand::SyncNow(reply)).is_err() {
+            return Response::error(format!("Watcher for {} stopped", name));
+        }
+
+        match result.await {
+            Ok(Ok(())) => Response::ok(),
+            Ok(Err(e)) => Response::error(e),
+            Err(_) => Response::error(format!("Watcher for {} stopped", name)),
+        }
+    }
+
+    /// Have the project's watcher compact its snapshots between two syncs.
+    pub async fn compact(&self, name: &str) -> std::result::Result<Compaction, String> {
+        let commands = self.watcher_commands(name)?;
+        let stopped = || format!("Watcher for {} stopped", name);
+
+        let (reply, result) = oneshot::channel();
+        commands.send(WatcherCommand::Compact(reply)).map_err(|_| stopped())?;
+        result.await.map_err(|_| stopped())?
+    }
+
+    fn watcher_commands(&self, name: &str) -> std::result::Result<mpsc::UnboundedSender<WatcherCommand>, String> {
+        let projects = self.projects.lock().unwrap();
+        match projects.get(name) {
+            None => Err(format!("Unknown project: {}", name)),
+            Some(ProjectEntry { watcher: None, .. }) => Err(format!("Watcher for {} is restarting", name)),
+            Some(ProjectEntry { watcher: Some(watcher), .. }) => Ok(watcher.commands.clone()),
+        }
+    }
+}
+
+/// Location of the control socket of the running pms
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("pms.sock"))
+}
+
+/// Serve control requests on the socket at `path` until the process exits.
+#[cfg(unix)]
+pub fn listen(path: &Path, controller: Arc<Controller>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(PMSError::ControlError(format!(
+                "Another pms is already listening on {}",
+                path.display()
+            )).into());
+        }
+        // Left behind by a pms that did not shut down cleanly
+        std::fs::remove_file(path)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let controller = controller.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_connection(stream, &controller).await {
+                            crate::logging::error(&format!("Control connection failed: {}", e));
+                        }
+                    });
+                }
+                Err(e) => crate::logging::error(&format!("Failed to accept control connection: {}", e)),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn serve_connection(stream: tokio::net::UnixStream, controller: &Controller) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => controller.handle(request).await,
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        };
+
+        let mut reply = serde_json::to_string(&response)?;
+        reply.push('\n');
+        writer.write_all(reply.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Send `request` to the running pms and wait for its reply.
+#[cfg(unix)]
+pub async fn send(path: &Path, request: &Request) -> Result<Response> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let stream = UnixStream::connect(path).await.map_err(|e| {
+        PMSError::ControlError(format!("Could not reach pms at {} ({}), is it running?", path.display(), e))
+    })?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+
+    let reply = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| PMSError::ControlError("Connection closed without a reply".to_string()))?;
+
+    Ok(serde_json::from_str(&reply)?)
+}
+
+#[cfg(not(unix))]
+pub fn listen(_path: &Path, _controller: Arc<Controller>) -> Result<()> {
+    Err(PMSError::ControlError("The control socket is only available on Unix".to_string()).into())
+}
+
+#[cfg(not(unix))]
+pub async fn send(_path: &Path, _request: &Request) -> Result<Response> {
+    Err(PMSError::ControlError("The control socket is only available on Unix".to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_format() {
+        assert_eq!(serde_json::to_string(&Request::Status).unwrap(), r#"{"command":"status"}"#);
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"command":"sync-now","project":"notes"}"#).unwrap(),
+            Request::SyncNow {
+                project: "notes".to_string()
+            }
+        );
+        assert_eq!(serde_json::to_string(&Response::ok()).unwrap(), r#"{"ok":true}"#);
+    }
+
+    #[tokio::test]
+    async fn test_controller() {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let controller = Controller::new(shutdown_tx);
+        controller.add_project("notes", Path::new("/tmp/notes"));
+
+        let status = controller.handle(Request::Status).await;
+        assert_eq!(status.projects[0].state, "restarting");
+
+        // Pausing is remembered until the watcher attaches
+        assert!(controller.handle(Request::Pause { project: "notes".to_string() }).await.ok);
+        let (commands, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let (_status_tx, status) = watch::channel(Default::default());
+        controller.attach("notes", WatcherHandle { commands, status });
+        assert!(matches!(received.try_recv(), Ok(WatcherCommand::Pause)));
+        assert_eq!(controller.handle(Request::Status).await.projects[0].state, "paused");
+
+        let unknown = controller.handle(Request::Resume { project: "other".to_string() }).await;
+        assert_eq!(unknown.error.as_deref(), Some("Unknown project: other"));
+
+        // Compaction is handed to the watcher, which runs it between syncs
+        assert!(controller.is_paused("notes"));
+        let compaction = Compaction { snapshots: 3, removed: 1 };
+        let (result, _) = tokio::join!(controller.compact("notes"), async {
+            let Some(WatcherCommand::Compact(reply)) = received.recv().await else {
+                panic!("expected a compact command");
+            };
+            reply.send(Ok(compaction)).unwrap();
+        });
+        assert_eq!(result, Ok(compaction));
+
+        assert!(controller.handle(Request::Stop).await.ok);
+        assert!(*shutdown_rx.borrow());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::backoff::Backoff;
+use crate::config::Config;
+use crate::control::{self, Controller};
+use crate::error::{self, PMSError, Result};
+use crate::git::GitHandler;
+use crate::logging;
+use crate::registry::{self, Project, Registry};
+use crate::watcher::FileWatcher;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+/// First delay before restarting a crashed watcher
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Longest delay between restarts of a watcher that keeps crashing
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(300);
+/// A watcher running this long is considered healthy again
+const HEALTHY_RUN: Duration = Duration::from_secs(600);
+
+/// Watch every registered project until SIGTERM or SIGINT.
+pub async fn run(config: Config, registry: Registry, verbose: bool) -> Result<()> {
+    if registry.projects.is_empty() {
+        return Err(PMSError::ConfigError("No projects registered".to_string()).into());
+    }
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let controller = Arc::new(Controller::new(shutdown_tx));
+    for project in &registry.projects {
+        controller.add_project(&project.name, &project.path);
+    }
+
+    let socket = control::socket_path()?;
+    control::listen(&socket, controller.clone())?;
+    stop_on_signal(controller.clone());
+
+    logging::info(&format!("Daemon watching {} project(s)", registry.projects.len()));
+    if config.retention.compact_interval_hours > 0 {
+        tokio::spawn(compact_periodically(
+            controller.clone(),
+            Duration::from_secs(config.retention.compact_interval_hours * 3600),
+            registry.projects.clone(),
+            shutdown_rx.clone(),
+        ));
+    }
+    supervise_all(controller, config, registry.projects, verbose, shutdown_rx).await;
+
+    let _ = std::fs::remove_file(&socket);
+    logging::success("Daemon stopped");
+    Ok(())
+}
+
+/// Ask `controller` to stop once SIGTERM or SIGINT arrives.
+pub fn stop_on_signal(controller: Arc<Controller>) {
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        logging::info("Received shutdown signal, syncing pending changes...");
+        controller.stop();
+    });
+}
+
+/// Resolves on Ctrl-C, and on SIGTERM where available.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            logging::error(&format!("Failed to listen for Ctrl-C: {}", e));
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = ctrl_c => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                logging::error(&format!("Failed to listen for SIGTERM: {}", e));
+                ctrl_c.await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    ctrl_c.await;
+}
+
+/// Run one supervised watcher per project until `shutdown` turns true.
+async fn supervise_all(
+    controller: Arc<Controller>,
+    config: Config,
+    projects: Vec<Project>,
+    verbose: bool,
+    shutdown: watch::Receiver<bool>,
+) {
+    let handles: Vec<_> = projects
+        .into_iter()
+        .map(|project| {
+            tokio::spawn(supervise(controller.clone(), project, config.clone(), verbose, shutdown.clone()))
+        })
+        .collect();
+
+    for handle in handles {
+        if let Err(e) = handle.await {
+            logging::error(&format!("Project supervisor failed: {}", e));
+        }
+    }
+}
+
+/// Keep a project's watcher running, restarting it with backoff when it fails.
+async fn supervise(
+    controller: Arc<Controller>,
+    project: Project,
+    config: Config,
+    verbose: bool,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut backoff = Backoff::new(RESTART_DELAY, MAX_RESTART_DELAY);
+
+    loop {
+        let started = Instant::now();
+        let result = tokio::spawn(run_project(
+            controller.clone(),
+            project.clone(),
+            config.clone(),
+            verbose,
+            shutdown.clone(),
+        ))
+        .await;
+        controller.detach(&project.name);
+
+        match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => logging::error(&format!("Watcher for {} failed: {}", project.name, e)),
+            Err(e) => logging::error(&format!("Watcher for {} crashed: {}", project.name, e)),
+        }
+
+        if *shutdown.borrow() {
+            return;
+        }
+
+        if started.elapsed() >= HEALTHY_RUN {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        logging::warning(&format!("Restarting watcher for {} in {}s", project.name, delay.as_secs()));
+
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = shutdown.changed() => return,
+        }
+    }
+}
+
+async fn run_project(
+    controller: Arc<Controller>,
+    project: Project,
+    config: Config,
+    verbose: bool,
+    shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    error::validate_path(&project.path)?;
+
+    let mut git_handler = GitHandler::new(project.path.clone(), config.clone());
+    git_handler.set_verbose(verbose);
+    git_handler.set_project_name(&project.name);
+
+    let mut watcher = FileWatcher::new(project.path.clone(), git_handler, config.sync_interval).await?;
+    watcher.start_watching()?;
+    let handle = watcher.handle();
+    tokio::spawn(registry::record_syncs(project.name.clone(), handle.status.clone()));
+    controller.attach(&project.name, handle);
+    logging::info(&format!("Watching {} at {}", project.name, project.path.display()));
+
+    watcher.watch_until(shutdown).await
+}
+
+/// Compact every project that is not paused each `compact_interval_hours` until shutdown.
+///
+/// Compaction runs inside each project's watcher, so it never overlaps a sync.
+async fn compact_periodically(
+    controller: Arc<Controller>,
+    interval: Duration,
+    projects: Vec<Project>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = shutdown.changed() => return,
+        }
+
+        for project in projects.iter().filter(|project| !controller.is_paused(&project.name)) {
+            match controller.compact(&project.name).await {
+                Ok(compaction) if compaction.removed > 0 => logging::info(&format!(
+                    "Compacted {}: folded {} of {} snapshot(s)",
+                    project.name, compaction.removed, compaction.snapshots
+                )),
+                Ok(_) => {}
+                Err(e) => logging::warning(&format!("Failed to compact {}: {}", project.name, e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::Request;
+    use crate::fake::remote_config;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_shutdown_syncs_every_project() {
+        let temp_dir = tempdir().unwrap();
+        let mut projects = Vec::new();
+
+        for name in ["alpha", "beta"] {
+            let path = temp_dir.path().join(name);
+            let remote = temp_dir.path().join(format!("{}.git", name));
+            fs::create_dir(&path).unwrap();
+
+            std::process::Command::new("git")
+                .args(["init", "--bare", remote.to_str().unwrap()])
+                .output()
+                .unwrap();
+            GitHandler::new(path.clone(), remote_config(&remote))
+                .init_repository()
+                .await
+                .unwrap();
+
+            projects.push(Project::new(name, &path, crate::config::ProviderKind::Git));
+        }
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let controller = Arc::new(Controller::new(shutdown_tx));
+        for project in &projects {
+            controller.add_project(&project.name, &project.path);
+        }
+        let mut config = remote_config(&temp_dir.path().join("alpha.git"));
+        config.sync_interval = 60;
+        let daemon = tokio::spawn(supervise_all(controller.clone(), config, projects.clone(), false, shutdown_rx));
+
+        // The sync interval is long, so only the shutdown sync can push these
+        sleep(Duration::from_millis(500)).await;
+        for project in &projects {
+            fs::write(project.path.join("notes.txt"), "pending").unwrap();
+        }
+        sleep(Duration::from_millis(500)).await;
+
+        let status = controller.handle(Request::Status).await;
+        assert!(status.projects.iter().all(|p| p.state == "watching" && p.pending == 1));
+
+        controller.handle(Request::Stop).await;
+        daemon.await.unwrap();
+
+        for project in &projects {
+            let output = std::process::Command::new("git")
+                .args(["ls-tree", "--name-only", "origin/main"])
+                .current_dir(&project.path)
+                .output()
+                .unwrap();
+            let files = String::from_utf8_lossy(&output.stdout);
+            assert!(files.contains("notes.txt"), "{} was not synced", project.name);
+        }
+    }
+}
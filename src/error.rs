@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,8 +10,33 @@ pub enum PMSError {
     #[error("Failed to push changes: {0}")]
     GitPushError(String),
 
-    #[error("Failed to create GitHub repository: {0}")]
-    GitHubApiError(String),
+    #[error("git {command} failed: {error}")]
+    GitError {
+        command: String,
+        error: String,
+    },
+
+    #[error("Sync conflict needs manual resolution: {0}")]
+    SyncConflict(String),
+
+    #[error("{provider} API request failed with status {status}: {error}")]
+    ApiError {
+        provider: String,
+        status: u16,
+        error: String,
+    },
+
+    #[error("{provider} API rate limit exceeded: {error}")]
+    RateLimited {
+        provider: String,
+        error: String,
+    },
+
+    #[error("{provider} API request failed: {error}")]
+    ProviderError {
+        provider: String,
+        error: String,
+    },
 
     #[error("Failed to read config file: {0}")]
     ConfigError(String),
@@ -38,13 +64,183 @@ pub enum PMSError {
 
     #[error("Security error: {0}")]
     SecurityError(String),
+
+    #[error("Control request failed: {0}")]
+    ControlError(String),
+
+    #[error("Remote repository already exists: {0}")]
+    RepositoryExists(String),
+
+    #[error("Template error: {0}")]
+    TemplateError(String),
+
+    #[error("SSH key error: {0}")]
+    SshKeyError(String),
 }
 
 // Use anyhow's Result type instead of std::result::Result
 pub type Result<T> = anyhow::Result<T>;
 
+/// Broad class of a failure, deciding the exit code and the hint shown for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    Auth,
+    Network,
+    RateLimit,
+    Conflict,
+    NotARepo,
+    Permission,
+    Config,
+    /// The remote repository name is taken
+    NameTaken,
+    Other,
+}
+
+impl ErrorKind {
+    /// Stable code printed with the error, for scripts and searching the docs
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::Auth => "auth",
+            ErrorKind::Network => "network",
+            ErrorKind::RateLimit => "rate-limit",
+            ErrorKind::Conflict => "conflict",
+            ErrorKind::NotARepo => "not-a-repo",
+            ErrorKind::Permission => "permission",
+            ErrorKind::Config => "config",
+            ErrorKind::NameTaken => "name-taken",
+            ErrorKind::Other => "other",
+        }
+    }
+
+    /// Process exit code; 2 is left to command line usage errors
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Config => 3,
+            ErrorKind::Auth => 4,
+            ErrorKind::Network => 5,
+            ErrorKind::RateLimit => 6,
+            ErrorKind::Conflict => 7,
+            ErrorKind::NotARepo => 8,
+            ErrorKind::Permission => 9,
+            ErrorKind::NameTaken => 10,
+        }
+    }
+
+    pub fn hint(self) -> Option<&'static str> {
+        let hint = match self {
+            ErrorKind::Auth => "Check the token with `pms config --token <token>`; it needs permission to create and push repositories",
+            ErrorKind::Network => "Check the network connection and the hosting URL; a running pms retries pushes by itself",
+            ErrorKind::RateLimit => "The hosting API rate limit was reached, wait a few minutes and try again",
+            ErrorKind::Conflict => "See `pms conflicts list` for parked versions and `pms conflicts resolve` to settle them",
+            ErrorKind::NotARepo => "Run `pms init` in the project directory first",
+            ErrorKind::Permission => "Check the permissions of the project directory and of ~/.config/pms",
+            ErrorKind::Config => "Check ~/.config/pms/config.toml and the command line arguments",
+            ErrorKind::NameTaken => "Pick another name with `--name`, or use `--on-conflict suffix` or `--on-conflict reuse`",
+            ErrorKind::Other => return None,
+        };
+        Some(hint)
+    }
+
+    /// Classify the error output of a failed git command.
+    pub fn from_git_output(output: &str) -> Self {
+        let output = output.to_lowercase();
+        let matches = |patterns: &[&str]| patterns.iter().any(|p| output.contains(p));
+
+        if matches(&[
+            "authentication failed",
+            "could not read username",
+            "could not read password",
+            "invalid username or password",
+            "permission denied (publickey",
+            "returned error: 401",
+            "returned error: 403",
+        ]) {
+            ErrorKind::Auth
+        } else if matches(&["rate limit"]) {
+            ErrorKind::RateLimit
+        } else if matches(&[
+            "could not resolve host",
+            "connection refused",
+            "connection timed out",
+            "operation timed out",
+            "network is unreachable",
+            "failed to connect",
+            "connection reset",
+            "early eof",
+        ]) {
+            ErrorKind::Network
+        } else if matches(&["not a git repository", "could not find repository"]) {
+            ErrorKind::NotARepo
+        } else if matches(&["[rejected]", "non-fast-forward", "fetch first", "conflict"]) {
+            ErrorKind::Conflict
+        } else if matches(&["permission denied"]) {
+            ErrorKind::Permission
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    /// Kind of the most specific pms error in the chain of `error`.
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .map(|cause| {
+                if let Some(e) = cause.downcast_ref::<PMSError>() {
+                    e.kind()
+                } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+                    io_error_kind(e)
+                } else {
+                    ErrorKind::Other
+                }
+            })
+            .find(|kind| *kind != ErrorKind::Other)
+            .unwrap_or(ErrorKind::Other)
+    }
+}
+
+fn io_error_kind(error: &std::io::Error) -> ErrorKind {
+    match error.kind() {
+        std::io::ErrorKind::PermissionDenied => ErrorKind::Permission,
+        _ => ErrorKind::Other,
+    }
+}
+
+impl PMSError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            PMSError::GitInitError(_)
+            | PMSError::WatchError { .. }
+            | PMSError::ControlError(_)
+            | PMSError::SshKeyError(_) => ErrorKind::Other,
+            PMSError::GitPushError(error) | PMSError::GitError { error, .. } => ErrorKind::from_git_output(error),
+            PMSError::SyncConflict(_) => ErrorKind::Conflict,
+            PMSError::RepositoryExists(_) => ErrorKind::NameTaken,
+            PMSError::ApiError { status, .. } => match status {
+                401 | 403 => ErrorKind::Auth,
+                // Creating a repository whose name is in use
+                409 | 422 => ErrorKind::NameTaken,
+                429 => ErrorKind::RateLimit,
+                500.. => ErrorKind::Network,
+                _ => ErrorKind::Other,
+            },
+            PMSError::RateLimited { .. } => ErrorKind::RateLimit,
+            PMSError::ProviderError { .. } => ErrorKind::Other,
+            PMSError::ConfigError(_)
+            | PMSError::InvalidConfig(_)
+            | PMSError::InvalidPath(_)
+            | PMSError::TemplateError(_) => ErrorKind::Config,
+            PMSError::IoError(e) => io_error_kind(e),
+            PMSError::NetworkError(_) => ErrorKind::Network,
+            PMSError::TokenError(_) => ErrorKind::Auth,
+            PMSError::SecurityError(_) => ErrorKind::Permission,
+        }
+    }
+}
+
 // Security validation functions
-pub fn validate_path(path: &PathBuf) -> Result<()> {
+pub fn validate_path(path: &Path) -> Result<()> {
     // Ensure path exists
     if !path.exists() {
         return Err(PMSError::InvalidPath(
@@ -79,6 +275,9 @@ pub fn validate_path(path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// Prefixes of the GitHub personal access tokens pms accepts
+pub const GITHUB_TOKEN_PREFIXES: [&str; 2] = ["ghp_", "github_pat_"];
+
 pub fn validate_token(token: &str) -> Result<()> {
     // Basic token validation
     if token.is_empty() {
@@ -94,7 +293,7 @@ pub fn validate_token(token: &str) -> Result<()> {
     }
 
     // Check for common token prefixes
-    if !token.starts_with("ghp_") && !token.starts_with("github_pat_") {
+    if !GITHUB_TOKEN_PREFIXES.iter().any(|prefix| token.starts_with(prefix)) {
         return Err(PMSError::TokenError(
             "Invalid GitHub token format: must start with 'ghp_' or 'github_pat_'".to_string(),
         ).into());
@@ -121,34 +320,110 @@ pub fn validate_git_config(username: &str, email: &str) -> Result<()> {
     Ok(())
 }
 
+/// Path of `path` relative to `root`, or `path` itself if it is not below it.
+pub fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return relative.to_path_buf();
+    }
+
+    // Events may carry canonical paths for a relative watch root
+    root.canonicalize()
+        .ok()
+        .and_then(|root| path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Longest repository name GitHub accepts
+pub const MAX_REPO_NAME_LENGTH: usize = 100;
+
 pub fn sanitize_repo_name(name: &str) -> String {
     // Remove special characters and spaces
     let sanitized: String = name
         .chars()
         .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
                 c.to_ascii_lowercase()
             } else {
                 '-'
             }
         })
+        .take(MAX_REPO_NAME_LENGTH)
         .collect();
 
     // Ensure it doesn't start or end with special characters
     sanitized.trim_matches('-').to_string()
 }
 
+/// Check a project name against GitHub's repository naming rules.
+pub fn validate_repo_name(name: &str) -> Result<()> {
+    let problem = if name.is_empty() {
+        "it is empty"
+    } else if name.len() > MAX_REPO_NAME_LENGTH {
+        "it is longer than 100 characters"
+    } else if name == "." || name == ".." {
+        "it is reserved"
+    } else if !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+        "only ASCII letters, digits, '-', '_' and '.' are allowed"
+    } else if name.to_ascii_lowercase().ends_with(".git") {
+        "it cannot end with .git"
+    } else {
+        return Ok(());
+    };
+
+    let suggestion = sanitize_repo_name(name);
+    let hint = if suggestion.is_empty() {
+        String::new()
+    } else {
+        format!(", try '{}'", suggestion)
+    };
+    Err(PMSError::InvalidConfig(format!("Invalid project name '{}': {}{}", name, problem, hint)).into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_error_kinds() {
+        let git = |error: &str| PMSError::GitError {
+            command: "push origin main".to_string(),
+            error: error.to_string(),
+        };
+        assert_eq!(git("fatal: Authentication failed for 'https://github.com/a/b.git/'").kind(), ErrorKind::Auth);
+        assert_eq!(git("git@github.com: Permission denied (publickey).").kind(), ErrorKind::Auth);
+        assert_eq!(git("fatal: unable to access '...': Could not resolve host: github.com").kind(), ErrorKind::Network);
+        assert_eq!(git("fatal: not a git repository (or any of the parent directories): .git").kind(), ErrorKind::NotARepo);
+        assert_eq!(git(" ! [rejected]        main -> main (fetch first)").kind(), ErrorKind::Conflict);
+        assert_eq!(git("error: unable to create file: Permission denied").kind(), ErrorKind::Permission);
+
+        let api = |status| PMSError::ApiError {
+            provider: "GitHub".to_string(),
+            status,
+            error: String::new(),
+        };
+        assert_eq!(api(401).kind(), ErrorKind::Auth);
+        assert_eq!(api(429).kind(), ErrorKind::RateLimit);
+        assert_eq!(api(422).kind(), ErrorKind::NameTaken);
+        assert_eq!(PMSError::RepositoryExists("notes on GitHub".to_string()).kind(), ErrorKind::NameTaken);
+        assert_eq!(PMSError::SyncConflict("README.md".to_string()).kind(), ErrorKind::Conflict);
+        assert_eq!(api(502).kind(), ErrorKind::Network);
+
+        // The most specific kind in the chain wins over wrapping context
+        let error = anyhow::Error::from(PMSError::NetworkError("timed out".to_string()))
+            .context("Failed to fetch from origin");
+        assert_eq!(ErrorKind::of(&error), ErrorKind::Network);
+        assert_eq!(ErrorKind::of(&anyhow::anyhow!("something odd")), ErrorKind::Other);
+
+        assert_eq!(ErrorKind::Network.exit_code(), 5);
+        assert_eq!(ErrorKind::Other.hint(), None);
+    }
+
     #[test]
     fn test_validate_path() {
         // Test valid directory
         let temp_dir = tempdir().unwrap();
-        assert!(validate_path(&temp_dir.path().to_path_buf()).is_ok());
+        assert!(validate_path(temp_dir.path()).is_ok());
 
         // Test non-existent path
         let non_existent = PathBuf::from("/non/existent/path");
@@ -187,5 +462,20 @@ mod tests {
         assert_eq!(sanitize_repo_name("test_repo"), "test_repo");
         assert_eq!(sanitize_repo_name("---test---"), "test");
         assert_eq!(sanitize_repo_name("!@#$%^"), "");
+        assert_eq!(sanitize_repo_name("café"), "caf");
+    }
+
+    #[test]
+    fn test_validate_repo_name() {
+        assert!(validate_repo_name("my-project").is_ok());
+        assert!(validate_repo_name("My_Project.v2").is_ok());
+
+        assert!(validate_repo_name("").is_err());
+        assert!(validate_repo_name("..").is_err());
+        assert!(validate_repo_name("project.git").is_err());
+        assert!(validate_repo_name(&"a".repeat(101)).is_err());
+
+        let error = validate_repo_name("My Project").unwrap_err().to_string();
+        assert!(error.contains("try 'my-project'"), "{}", error);
     }
 }
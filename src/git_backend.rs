@@ -0,0 +1,374 @@
+use crate::error::{PMSError, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// A path `git status` reports as changed or untracked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Previous path of a renamed or copied file
+    pub source: Option<String>,
+}
+
+/// The git operations a sync is made of.
+///
+/// Everything else pms does with git goes through [`GitBackend::run`].
+#[async_trait]
+pub trait GitBackend: Send + Sync {
+    /// Changed and untracked files, including every file in untracked directories
+    async fn status(&self) -> Result<Vec<StatusEntry>>;
+
+    /// Stage the current state of `paths`, including deletions
+    async fn add(&self, paths: &[String]) -> Result<()>;
+
+    /// Remove `paths` from the index again, keeping the working tree
+    async fn unstage(&self, paths: &[&str]) -> Result<()>;
+
+    /// Paths that differ between `HEAD` and the index
+    async fn staged_paths(&self) -> Result<Vec<String>>;
+
+    /// Patch of the staged changes without context lines
+    async fn staged_diff(&self) -> Result<String>;
+
+    async fn commit(&self, message: &str) -> Result<()>;
+
+    /// Object id `reference` points at, or `None` if it does not exist
+    async fn resolve(&self, reference: &str) -> Result<Option<String>>;
+
+    /// Whether `ancestor` is reachable from `descendant` (or the same commit)
+    async fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool>;
+
+    /// Number of commits reachable from `to` but not from `from`
+    async fn count_commits(&self, from: Option<&str>, to: &str) -> Result<usize>;
+
+    async fn fetch(&self, remote: &str) -> Result<()>;
+
+    async fn push(&self, remote: &str, refspec: &str, force: bool) -> Result<()>;
+
+    /// Run any other git command and return its output
+    async fn run(&self, args: &[&str]) -> Result<String>;
+
+    /// The same repository, staging in the index file `index` and committing
+    /// onto `reference` instead of the checked out branch.
+    ///
+    /// Staged paths and diffs compare against `reference`, which must exist.
+    /// HEAD, the branch and the default index are left alone.
+    fn detached(&self, index: &Path, reference: &str) -> Box<dyn GitBackend>;
+}
+
+/// The backend pms was built with: libgit2 with the `native-git` feature,
+/// the `git` command otherwise.
+pub fn open(repo_path: &Path) -> Box<dyn GitBackend> {
+    #[cfg(feature = "native-git")]
+    {
+        Box::new(crate::native_git::NativeBackend::new(repo_path.to_path_buf()))
+    }
+    #[cfg(not(feature = "native-git"))]
+    {
+        Box::new(CliBackend::new(repo_path.to_path_buf()))
+    }
+}
+
+/// Runs the `git` command for every operation.
+pub struct CliBackend {
+    repo_path: PathBuf,
+    /// Index file and reference of a [detached](GitBackend::detached) backend
+    detached: Option<(PathBuf, String)>,
+}
+
+impl CliBackend {
+    pub fn new(repo_path: PathBuf) -> Self {
+        Self {
+            repo_path,
+            detached: None,
+        }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("git");
+        command.args(args).current_dir(&self.repo_path);
+        if let Some((index, _)) = &self.detached {
+            command.env("GIT_INDEX_FILE", index);
+        }
+        command
+    }
+
+    /// `diff --cached` compares against HEAD unless detached
+    fn cached_diff<'a>(&'a self, args: &[&'a str]) -> Vec<&'a str> {
+        let mut args = args.to_vec();
+        if let Some((_, reference)) = &self.detached {
+            args.push(reference);
+        }
+        args
+    }
+
+    /// Commit the index onto `reference` without touching HEAD.
+    async fn commit_detached(&self, reference: &str, message: &str) -> Result<()> {
+        let tree = self.run(&["write-tree"]).await?.trim().to_string();
+        let parent = self.resolve(reference).await?;
+        if self.resolve(&format!("{}^{{tree}}", reference)).await?.as_deref() == Some(tree.as_str()) {
+            return Err(PMSError::GitError {
+                command: "commit".to_string(),
+                error: "nothing to commit, working tree clean".to_string(),
+            }.into());
+        }
+
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+        if let Some(parent) = &parent {
+            args.extend(["-p", parent.as_str()]);
+        }
+        // Commits made on the checked out branch meanwhile stay reachable
+        let head = self.resolve("HEAD").await?;
+        if let (Some(head), Some(parent)) = (&head, &parent) {
+            if !self.is_ancestor(head, parent).await? {
+                args.extend(["-p", head.as_str()]);
+            }
+        }
+        let commit = self.run(&args).await?.trim().to_string();
+
+        let mut args = vec!["update-ref", reference, commit.as_str()];
+        args.extend(parent.as_deref());
+        self.run(&args).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GitBackend for CliBackend {
+    async fn status(&self) -> Result<Vec<StatusEntry>> {
+        let status = self.run(&["status", "--porcelain", "-z", "--untracked-files=all"]).await?;
+
+        let mut changes = Vec::new();
+        let mut entries = status.split('\0').filter(|entry| !entry.is_empty());
+        while let Some(entry) = entries.next() {
+            let Some(path) = entry.get(3..) else { continue };
+
+            // Renames and copies are followed by their source path
+            let source = if entry.starts_with('R') || entry.starts_with('C') {
+                entries.next().map(String::from)
+            } else {
+                None
+            };
+            changes.push(StatusEntry {
+                path: path.to_string(),
+                source,
+            });
+        }
+
+        Ok(changes)
+    }
+
+    async fn add(&self, paths: &[String]) -> Result<()> {
+        let mut args = vec!["add", "-A", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        self.run(&args).await?;
+        Ok(())
+    }
+
+    async fn unstage(&self, paths: &[&str]) -> Result<()> {
+        // Unborn branches have no HEAD to reset the index to
+        let mut args = if let Some((_, reference)) = &self.detached {
+            vec!["reset", "-q", reference, "--"]
+        } else if self.resolve("HEAD").await?.is_some() {
+            vec!["reset", "-q", "--"]
+        } else {
+            vec!["rm", "--cached", "-q", "--"]
+        };
+        args.extend(paths);
+        self.run(&args).await?;
+        Ok(())
+    }
+
+    async fn staged_paths(&self) -> Result<Vec<String>> {
+        let staged = self.run(&self.cached_diff(&["diff", "--cached", "--name-only", "-z"])).await?;
+        Ok(staged
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    async fn staged_diff(&self) -> Result<String> {
+        // Explicit prefixes, since diff.noprefix or diff.mnemonicPrefix would change the headers
+        let args = [
+            "-c", "core.quotePath=false",
+            "diff", "--cached", "-U0", "--no-color", "--no-ext-diff", "--src-prefix=a/", "--dst-prefix=b/",
+        ];
+        self.run(&self.cached_diff(&args)).await
+    }
+
+    async fn commit(&self, message: &str) -> Result<()> {
+        if let Some((_, reference)) = &self.detached {
+            return self.commit_detached(reference, message).await;
+        }
+        self.run(&["commit", "-m", message]).await?;
+        Ok(())
+    }
+
+    async fn resolve(&self, reference: &str) -> Result<Option<String>> {
+        let output = self
+            .command(&["rev-parse", "--verify", "--quiet", reference])
+            .output()
+            .await?;
+
+        // `--quiet` exits with 1 and no output for a missing reference
+        if output.status.success() {
+            Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+        } else if output.stderr.is_empty() {
+            Ok(None)
+        } else {
+            Err(PMSError::GitError {
+                command: format!("rev-parse --verify {}", reference),
+                error: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }.into())
+        }
+    }
+
+    async fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        let status = self
+            .command(&["merge-base", "--is-ancestor", ancestor, descendant])
+            .status()
+            .await?;
+
+        match status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(PMSError::GitError {
+                command: format!("merge-base --is-ancestor {} {}", ancestor, descendant),
+                error: format!("exited with {}", status),
+            }.into()),
+        }
+    }
+
+    async fn count_commits(&self, from: Option<&str>, to: &str) -> Result<usize> {
+        let range = match from {
+            Some(from) => format!("{}..{}", from, to),
+            None => to.to_string(),
+        };
+        let count = self.run(&["rev-list", "--count", &range]).await?;
+        count.trim().parse().map_err(|e| {
+            PMSError::GitError {
+                command: format!("rev-list --count {}", range),
+                error: format!("unexpected output {:?}: {}", count.trim(), e),
+            }.into()
+        })
+    }
+
+    async fn fetch(&self, remote: &str) -> Result<()> {
+        self.run(&["fetch", remote]).await?;
+        Ok(())
+    }
+
+    async fn push(&self, remote: &str, refspec: &str, force: bool) -> Result<()> {
+        let mut args = vec!["push"];
+        if force {
+            args.push("-f");
+        }
+        args.extend([remote, refspec]);
+        self.run(&args).await?;
+        Ok(())
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String> {
+        let output = self
+            .command(args)
+            .output()
+            .await
+            .map_err(|e| PMSError::GitError {
+                command: args.join(" "),
+                error: e.to_string(),
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let error = if stderr.is_empty() { stdout } else { stderr };
+            return Err(PMSError::GitError {
+                command: args.join(" "),
+                error: error.trim().to_string(),
+            }.into());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn detached(&self, index: &Path, reference: &str) -> Box<dyn GitBackend> {
+        Box::new(Self {
+            repo_path: self.repo_path.clone(),
+            detached: Some((index.to_path_buf(), reference.to_string())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// Walk a backend through the operations of a sync
+    async fn exercise(backend: &dyn GitBackend, root: &Path) {
+        backend.run(&["init", "-q", "-b", "main"]).await.unwrap();
+        backend.run(&["config", "user.name", "test-user"]).await.unwrap();
+        backend.run(&["config", "user.email", "test@example.com"]).await.unwrap();
+        assert_eq!(backend.resolve("HEAD").await.unwrap(), None);
+
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/notes.md"), "first\n").unwrap();
+        fs::write(root.join("secret.txt"), "token\n").unwrap();
+        let mut status = backend.status().await.unwrap();
+        status.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(status[0], StatusEntry { path: "docs/notes.md".to_string(), source: None });
+        assert_eq!(status.len(), 2);
+
+        backend.add(&["docs/notes.md".to_string(), "secret.txt".to_string()]).await.unwrap();
+        backend.unstage(&["secret.txt"]).await.unwrap();
+        assert_eq!(backend.staged_paths().await.unwrap(), ["docs/notes.md"]);
+        assert!(backend.staged_diff().await.unwrap().contains("+++ b/docs/notes.md\n@@ -0,0 +1 @@\n+first\n"));
+
+        backend.commit("Add notes\n\nFirst version").await.unwrap();
+        let first = backend.resolve("HEAD").await.unwrap().unwrap();
+        assert_eq!(backend.run(&["log", "-1", "--format=%B"]).await.unwrap().trim(), "Add notes\n\nFirst version");
+
+        fs::remove_file(root.join("docs/notes.md")).unwrap();
+        backend.add(&["docs/notes.md".to_string()]).await.unwrap();
+        assert_eq!(backend.staged_paths().await.unwrap(), ["docs/notes.md"]);
+        backend.commit("Remove notes").await.unwrap();
+
+        assert!(backend.is_ancestor(&first, "HEAD").await.unwrap());
+        assert!(!backend.is_ancestor("HEAD", &first).await.unwrap());
+        assert_eq!(backend.count_commits(Some(&first), "HEAD").await.unwrap(), 1);
+        assert_eq!(backend.count_commits(None, "HEAD").await.unwrap(), 2);
+
+        // A detached backend commits beside the branch
+        let head = backend.resolve("HEAD").await.unwrap();
+        backend.run(&["update-ref", "refs/heads/autosave", "HEAD"]).await.unwrap();
+        let detached = backend.detached(&root.join(".git/autosave.index"), "refs/heads/autosave");
+        detached.run(&["read-tree", "refs/heads/autosave"]).await.unwrap();
+        fs::write(root.join("draft.md"), "draft\n").unwrap();
+        detached.add(&["draft.md".to_string()]).await.unwrap();
+        assert_eq!(detached.staged_paths().await.unwrap(), ["draft.md"]);
+        detached.commit("Autosave").await.unwrap();
+        assert!(detached.commit("Autosave").await.is_err());
+
+        assert_eq!(backend.resolve("HEAD").await.unwrap(), head);
+        assert!(backend.staged_paths().await.unwrap().is_empty());
+        assert_eq!(backend.count_commits(Some("HEAD"), "refs/heads/autosave").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cli_backend() {
+        let temp_dir = tempdir().unwrap();
+        exercise(&CliBackend::new(temp_dir.path().to_path_buf()), temp_dir.path()).await;
+    }
+
+    #[cfg(feature = "native-git")]
+    #[tokio::test]
+    async fn test_native_backend() {
+        let temp_dir = tempdir().unwrap();
+        let backend = crate::native_git::NativeBackend::new(temp_dir.path().to_path_buf());
+        exercise(&backend, temp_dir.path()).await;
+    }
+}
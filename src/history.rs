@@ -0,0 +1,156 @@
+use crate::config::RetentionConfig;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::collections::HashSet;
+
+/// A commit pms made, as listed by `pms history`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub id: String,
+    pub time: DateTime<Local>,
+    pub subject: String,
+    /// Changed files, limited to the path history was asked for
+    pub files: Vec<String>,
+}
+
+impl Snapshot {
+    /// Parse one record of `git log --format=%H%x09%ct%x09%s --name-only`.
+    pub fn parse(record: &str) -> Option<Self> {
+        let mut lines = record.lines().filter(|line| !line.is_empty());
+        let mut fields = lines.next()?.splitn(3, '\t');
+        let id = fields.next()?.to_string();
+        let time = Local.timestamp_opt(fields.next()?.parse().ok()?, 0).single()?;
+        let subject = fields.next().unwrap_or_default().to_string();
+
+        Some(Self {
+            id,
+            time,
+            subject,
+            files: lines.map(String::from).collect(),
+        })
+    }
+
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+}
+
+/// Parse the time given to `pms restore --at`.
+///
+/// Accepts `2024-03-01`, `2024-03-01 10:15`, `2024-03-01 10:15:30`, RFC 3339
+/// and durations before `now` like `30m`, `2h`, `3d` or `1w`.
+pub fn parse_time(input: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let input = input.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Some(time.with_timezone(&Local));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(input, format) {
+            return Local.from_local_datetime(&time).earliest();
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest();
+    }
+
+    let unit = input.chars().last()?;
+    let amount: i64 = input[..input.len() - unit.len_utf8()].trim().parse().ok()?;
+    let ago = match unit {
+        's' => Duration::seconds(amount),
+        'm' => Duration::minutes(amount),
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        'w' => Duration::weeks(amount),
+        _ => return None,
+    };
+    Some(now - ago)
+}
+
+/// Outcome of `pms compact`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compaction {
+    /// Snapshots compaction could rewrite
+    pub snapshots: usize,
+    /// Of those, the ones folded into a later snapshot
+    pub removed: usize,
+}
+
+/// Which of `times`, ordered newest first, `policy` keeps at `now`.
+///
+/// Each snapshot older than `keep_all_hours` falls into an hour, day or week
+/// depending on its age, and only the newest snapshot of each one is kept.
+pub fn retained(times: &[DateTime<Local>], policy: &RetentionConfig, now: DateTime<Local>) -> Vec<bool> {
+    let mut buckets = HashSet::new();
+
+    times
+        .iter()
+        .map(|time| {
+            let age = now - *time;
+            let bucket = if age < Duration::hours(policy.keep_all_hours as i64) {
+                return true;
+            } else if age < Duration::days(policy.hourly_days as i64) {
+                time.format("h%Y-%m-%d %H")
+            } else if age < Duration::days(policy.daily_days as i64) {
+                time.format("d%Y-%m-%d")
+            } else {
+                time.format("w%G-%V")
+            };
+            buckets.insert(bucket.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(parse_time("2h", now), Some(Local.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap()));
+        assert_eq!(parse_time("1w", now), Some(Local.with_ymd_and_hms(2024, 2, 23, 12, 0, 0).unwrap()));
+        assert_eq!(parse_time("2024-02-28", now), Some(Local.with_ymd_and_hms(2024, 2, 28, 0, 0, 0).unwrap()));
+        assert_eq!(
+            parse_time("2024-02-28 09:30", now),
+            Some(Local.with_ymd_and_hms(2024, 2, 28, 9, 30, 0).unwrap())
+        );
+        assert!(parse_time("2024-02-28T09:30:00+00:00", now).is_some());
+        assert_eq!(parse_time("a1b2c3d", now), None);
+        assert_eq!(parse_time("h", now), None);
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let snapshot = Snapshot::parse("4f2a9c1e\t1709287200\tUpdate notes.md\n\nnotes.md\ndocs/a.md\n").unwrap();
+        assert_eq!(snapshot.short_id(), "4f2a9c1");
+        assert_eq!(snapshot.subject, "Update notes.md");
+        assert_eq!(snapshot.files, ["notes.md", "docs/a.md"]);
+        assert_eq!(snapshot.time.timestamp(), 1709287200);
+        assert!(Snapshot::parse("").is_none());
+    }
+
+    #[test]
+    fn test_retained() {
+        let now = Local.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap();
+        let times = [
+            now - Duration::minutes(1),
+            now - Duration::minutes(2),
+            // Same hour two days ago: only the newer one stays
+            Local.with_ymd_and_hms(2024, 3, 18, 9, 40, 0).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 18, 9, 10, 0).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 18, 8, 50, 0).unwrap(),
+            // Same day two weeks ago
+            Local.with_ymd_and_hms(2024, 3, 6, 18, 0, 0).unwrap(),
+            Local.with_ymd_and_hms(2024, 3, 6, 9, 0, 0).unwrap(),
+            // Same ISO week two months ago
+            Local.with_ymd_and_hms(2024, 1, 10, 9, 0, 0).unwrap(),
+            Local.with_ymd_and_hms(2024, 1, 8, 9, 0, 0).unwrap(),
+        ];
+
+        assert_eq!(
+            retained(&times, &RetentionConfig::default(), now),
+            [true, true, true, false, true, true, false, true, false]
+        );
+    }
+}
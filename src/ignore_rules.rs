@@ -0,0 +1,197 @@
+use crate::error::{relative_path, PMSError, Result};
+use crate::logging;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+use std::path::{Component, Path, PathBuf};
+
+/// File with project specific patterns pms never commits
+pub const PMSIGNORE_FILE: &str = ".pmsignore";
+
+/// Decides which paths pms leaves alone, using gitignore semantics.
+///
+/// The config `ignore_patterns` and `.pmsignore` take precedence over the
+/// repository's `.gitignore` files, so a `.gitignore` negation cannot expose
+/// a secret. `.pmsignore` is read after the config patterns and may negate them.
+pub struct IgnoreRules {
+    root: PathBuf,
+    patterns: Vec<String>,
+    pms: Gitignore,
+    /// `.gitignore` matchers keyed by their directory, shallowest first
+    gitignores: Vec<(PathBuf, Gitignore)>,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let mut rules = Self {
+            root: root.to_path_buf(),
+            patterns: patterns.to_vec(),
+            pms: Gitignore::empty(),
+            gitignores: Vec::new(),
+        };
+        rules.reload();
+        rules
+    }
+
+    /// Re-read `.pmsignore` and all `.gitignore` files below the root.
+    pub fn reload(&mut self) {
+        let mut builder = GitignoreBuilder::new(".");
+        for pattern in &self.patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                logging::warning(&format!("Invalid ignore pattern '{}': {}", pattern, e));
+            }
+        }
+
+        let pmsignore = self.root.join(PMSIGNORE_FILE);
+        if pmsignore.exists() {
+            if let Some(e) = builder.add(&pmsignore) {
+                logging::warning(&format!("Invalid pattern in {}: {}", PMSIGNORE_FILE, e));
+            }
+        }
+
+        self.pms = build(&builder);
+        self.gitignores = self.collect_gitignores();
+    }
+
+    fn collect_gitignores(&self) -> Vec<(PathBuf, Gitignore)> {
+        let walker = WalkBuilder::new(&self.root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build();
+
+        let mut gitignores: Vec<(PathBuf, Gitignore)> = walker
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name() == ".gitignore")
+            .filter_map(|entry| {
+                let dir = relative_path(entry.path().parent()?, &self.root);
+                let mut builder = GitignoreBuilder::new(".");
+                if let Some(e) = builder.add(entry.path()) {
+                    logging::warning(&format!("Invalid pattern in {}: {}", entry.path().display(), e));
+                }
+                Some((dir, build(&builder)))
+            })
+            .collect();
+
+        gitignores.sort_by_key(|(dir, _)| dir.components().count());
+        gitignores
+    }
+
+    /// Whether the watcher should skip `path` and staging should leave it out.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = relative_path(path, &self.root);
+        if path.has_root() || path.components().any(|c| c == Component::ParentDir) {
+            // Outside of the project
+            return false;
+        }
+
+        if path.components().any(|c| c.as_os_str() == ".git") {
+            return true;
+        }
+
+        match self.pms.matched_path_or_any_parents(&path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+
+        // Deeper .gitignore files override shallower ones
+        for (dir, gitignore) in self.gitignores.iter().rev() {
+            if let Ok(sub_path) = path.strip_prefix(dir) {
+                match gitignore.matched_path_or_any_parents(sub_path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        false
+    }
+}
+
+fn build(builder: &GitignoreBuilder) -> Gitignore {
+    builder.build().unwrap_or_else(|e| {
+        logging::warning(&format!("Failed to build ignore rules: {}", e));
+        Gitignore::empty()
+    })
+}
+
+/// Check that configured ignore patterns are valid gitignore globs.
+pub fn validate_patterns(patterns: &[String]) -> Result<()> {
+    let mut builder = GitignoreBuilder::new(".");
+    for pattern in patterns {
+        builder.add_line(None, pattern).map_err(|e| {
+            PMSError::InvalidConfig(format!("Invalid ignore pattern '{}': {}", pattern, e))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn patterns() -> Vec<String> {
+        crate::config::SecurityConfig::default().ignore_patterns
+    }
+
+    #[test]
+    fn test_config_patterns() {
+        let temp_dir = tempdir().unwrap();
+        let rules = IgnoreRules::new(temp_dir.path(), &patterns());
+
+        assert!(rules.is_ignored(&temp_dir.path().join(".env"), false));
+        assert!(rules.is_ignored(&temp_dir.path().join("config/prod.env"), false));
+        assert!(rules.is_ignored(&temp_dir.path().join("keys/id_rsa"), false));
+        assert!(rules.is_ignored(&temp_dir.path().join(".git/index"), false));
+        assert!(!rules.is_ignored(&temp_dir.path().join("src/main.rs"), false));
+        assert!(!rules.is_ignored(Path::new("/elsewhere/.env"), false));
+    }
+
+    #[test]
+    fn test_gitignore_semantics() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("web/build")).unwrap();
+        fs::write(root.join(".gitignore"), "/target\nbuild/\n**/cache/**\n*.tmp\n!keep.tmp\n").unwrap();
+        fs::write(root.join("web/.gitignore"), "!build/\n").unwrap();
+
+        let rules = IgnoreRules::new(root, &patterns());
+
+        // Anchored to the root
+        assert!(rules.is_ignored(&root.join("target/debug/pms"), false));
+        assert!(!rules.is_ignored(&root.join("src/target"), false));
+
+        // Directory-only patterns and nested overrides
+        assert!(rules.is_ignored(&root.join("build"), true));
+        assert!(!rules.is_ignored(&root.join("web/build"), true));
+
+        assert!(rules.is_ignored(&root.join("a/cache/b/c.txt"), false));
+        assert!(rules.is_ignored(&root.join("x.tmp"), false));
+        assert!(!rules.is_ignored(&root.join("keep.tmp"), false));
+    }
+
+    #[test]
+    fn test_pmsignore() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(PMSIGNORE_FILE), "secrets/\n!debug.log\n").unwrap();
+        // A .gitignore negation cannot re-include a configured pattern
+        fs::write(root.join(".gitignore"), "!*.env\n").unwrap();
+
+        let rules = IgnoreRules::new(root, &patterns());
+
+        assert!(rules.is_ignored(&root.join("secrets/token.txt"), false));
+        assert!(rules.is_ignored(&root.join("app.log"), false));
+        assert!(!rules.is_ignored(&root.join("debug.log"), false));
+        assert!(rules.is_ignored(&root.join(".env"), false));
+    }
+
+    #[test]
+    fn test_validate_patterns() {
+        assert!(validate_patterns(&patterns()).is_ok());
+        assert!(validate_patterns(&["[".to_string()]).is_err());
+    }
+}
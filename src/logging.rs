@@ -17,6 +17,11 @@ pub fn error(msg: &str) {
     eprintln!("{} {} {}", time.dimmed(), "ERROR".red(), msg);
 }
 
+pub fn hint(msg: &str) {
+    let time = Local::now().format("%H:%M:%S").to_string();
+    eprintln!("{} {} {}", time.dimmed(), "HINT".cyan(), msg);
+}
+
 pub fn warning(msg: &str) {
     let time = Local::now().format("%H:%M:%S").to_string();
     println!("{} {} {}", time.dimmed(), "WARN".yellow(), msg);
@@ -30,6 +35,7 @@ pub fn status_change(path: &Path, change_type: &str, color: colored::Color) {
         "modified" => "~",
         "renamed" => "→",
         "deleted" => "-",
+        "skipped" => "!",
         _ => " "
     };
     println!(
@@ -51,7 +57,7 @@ pub fn git_operation(operation: &str) {
     );
 }
 
-pub fn startup_message(path: &Path, username: &str, repo_name: &str) {
+pub fn startup_message(path: &Path, repo_url: &str) {
     let time = Local::now().format("%H:%M:%S").to_string();
     println!("\n{} {} Project Management System (PMS)", time.dimmed(), "STARTUP".bright_blue());
     println!("{} {} Monitoring directory: {}", 
@@ -62,7 +68,7 @@ pub fn startup_message(path: &Path, username: &str, repo_name: &str) {
     println!("{} {} Repository: {}", 
         time.dimmed(),
         "STARTUP".bright_blue(),
-        format!("https://github.com/{}/{}", username, repo_name).cyan()
+        repo_url.cyan()
     );
     println!("{} {} Press Ctrl+C to stop\n", 
         time.dimmed(),
@@ -0,0 +1,323 @@
+//! A local stand-in for the GitHub REST API.
+//!
+//! [`MockGitHub::start`] serves the endpoints pms uses on a random port
+//! of 127.0.0.1; pointing `hosting.api_url` at [`MockGitHub::url`] lets
+//! tests run provider calls and `init` without network access.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Requests allowed per rate limit window, as for authenticated users
+const RATE_LIMIT: u32 = 5000;
+
+/// A request the server received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn new(status: u16, body: Value) -> Self {
+        Self { status, body }
+    }
+
+    fn message(status: u16, message: &str) -> Self {
+        Self::new(status, json!({
+            "message": message,
+            "documentation_url": "https://docs.github.com/rest",
+        }))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        _ => "Unknown",
+    }
+}
+
+struct State {
+    token: String,
+    login: String,
+    /// Repositories by `owner/name`
+    repositories: BTreeMap<String, Value>,
+    rate_limit_remaining: u32,
+    requests: Vec<MockRequest>,
+}
+
+impl State {
+    fn handle(&mut self, method: &str, path: &str, authorization: Option<&str>, body: Value) -> Response {
+        self.requests.push(MockRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.clone(),
+        });
+
+        if authorization != Some(format!("Bearer {}", self.token).as_str()) {
+            return Response::message(401, "Bad credentials");
+        }
+        if self.rate_limit_remaining == 0 {
+            return Response::message(403, "API rate limit exceeded for user ID 1.");
+        }
+        self.rate_limit_remaining -= 1;
+
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            ("GET", ["user"]) => Response::new(200, json!({ "login": self.login, "id": 1, "type": "User" })),
+            ("POST", ["user", "repos"]) => {
+                let owner = self.login.clone();
+                self.create(&owner, body)
+            }
+            ("POST", ["orgs", org, "repos"]) => self.create(org, body),
+            ("GET", ["repos", owner, name]) => match self.repositories.get(&format!("{}/{}", owner, name)) {
+                Some(repository) => Response::new(200, repository.clone()),
+                None => Response::message(404, "Not Found"),
+            },
+            ("PUT", ["repos", owner, name, "topics"]) => {
+                match self.repositories.get_mut(&format!("{}/{}", owner, name)) {
+                    Some(repository) => {
+                        repository["topics"] = body["names"].clone();
+                        Response::new(200, json!({ "names": body["names"] }))
+                    }
+                    None => Response::message(404, "Not Found"),
+                }
+            }
+            ("POST", ["repos", owner, name, "keys"]) => self.add_key(&format!("{}/{}", owner, name), body),
+            ("DELETE", ["repos", owner, name]) => match self.repositories.remove(&format!("{}/{}", owner, name)) {
+                Some(_) => Response::new(204, Value::Null),
+                None => Response::message(404, "Not Found"),
+            },
+            _ => Response::message(404, "Not Found"),
+        }
+    }
+
+    fn add_key(&mut self, full_name: &str, body: Value) -> Response {
+        let key = body["key"].clone();
+        let in_use = self
+            .repositories
+            .values()
+            .any(|repository| repository["keys"].as_array().is_some_and(|keys| keys.iter().any(|k| k["key"] == key)));
+        let Some(repository) = self.repositories.get_mut(full_name) else {
+            return Response::message(404, "Not Found");
+        };
+        if in_use {
+            return Response::new(422, json!({
+                "message": "Validation Failed",
+                "errors": [{ "resource": "PublicKey", "code": "custom", "field": "key", "message": "key is already in use" }],
+                "documentation_url": "https://docs.github.com/rest/deploy-keys/deploy-keys#create-a-deploy-key",
+            }));
+        }
+
+        let deploy_key = json!({
+            "id": repository["keys"].as_array().map_or(0, Vec::len) + 1,
+            "title": body["title"],
+            "key": key,
+            "read_only": body["read_only"].as_bool().unwrap_or(true),
+        });
+        match repository["keys"].as_array_mut() {
+            Some(keys) => keys.push(deploy_key.clone()),
+            None => repository["keys"] = json!([deploy_key.clone()]),
+        }
+        Response::new(201, deploy_key)
+    }
+
+    fn create(&mut self, owner: &str, body: Value) -> Response {
+        let Some(name) = body["name"].as_str() else {
+            return Response::message(422, "Repository creation failed.");
+        };
+
+        let full_name = format!("{}/{}", owner, name);
+        if self.repositories.contains_key(&full_name) {
+            return Response::new(422, json!({
+                "message": "Repository creation failed.",
+                "errors": [{
+                    "resource": "Repository",
+                    "code": "custom",
+                    "field": "name",
+                    "message": "name already exists on this account",
+                }],
+                "documentation_url": "https://docs.github.com/rest/repos/repos#create-a-repository-for-the-authenticated-user",
+            }));
+        }
+
+        let repository = json!({
+            "name": name,
+            "full_name": full_name,
+            "owner": { "login": owner },
+            "private": body["private"].as_bool().unwrap_or(false),
+            "visibility": body["visibility"].as_str().unwrap_or("public"),
+            "description": body["description"],
+            "homepage": body["homepage"],
+            "topics": [],
+            "html_url": format!("https://github.com/{}", full_name),
+            "clone_url": format!("https://github.com/{}.git", full_name),
+        });
+        self.repositories.insert(full_name, repository.clone());
+        Response::new(201, repository)
+    }
+}
+
+/// A GitHub API server for one test, stopped when dropped.
+pub struct MockGitHub {
+    url: String,
+    state: Arc<Mutex<State>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockGitHub {
+    /// Serve requests authenticated with `token` as the user `login`.
+    pub async fn start(login: &str, token: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State {
+            token: token.to_string(),
+            login: login.to_string(),
+            repositories: BTreeMap::new(),
+            rate_limit_remaining: RATE_LIMIT,
+            requests: Vec::new(),
+        }));
+
+        let server_state = state.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let _ = serve(stream, state).await;
+                });
+            }
+        });
+
+        Self { url, state, server }
+    }
+
+    /// Base URL for `hosting.api_url`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Pretend `owner/name` was created earlier.
+    pub fn add_repository(&self, owner: &str, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.create(owner, json!({ "name": name, "private": true, "visibility": "private" }));
+    }
+
+    pub fn repository(&self, owner: &str, name: &str) -> Option<Value> {
+        self.state.lock().unwrap().repositories.get(&format!("{}/{}", owner, name)).cloned()
+    }
+
+    /// Requests left before the server answers with rate limit errors.
+    pub fn set_rate_limit_remaining(&self, remaining: u32) {
+        self.state.lock().unwrap().rate_limit_remaining = remaining;
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockGitHub {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Answer one HTTP/1.1 request and close the connection.
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let (response, remaining) = {
+        let mut state = state.lock().unwrap();
+        let response = state.handle(&method, &path, headers.get("authorization").map(String::as_str), body);
+        (response, state.rate_limit_remaining)
+    };
+
+    let body = if response.body.is_null() {
+        String::new()
+    } else {
+        response.body.to_string()
+    };
+    let reply = format!(
+        "HTTP/1.1 {} {}\r\n\
+         content-type: application/json; charset=utf-8\r\n\
+         content-length: {}\r\n\
+         x-ratelimit-limit: {}\r\n\
+         x-ratelimit-remaining: {}\r\n\
+         x-ratelimit-reset: {}\r\n\
+         connection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        RATE_LIMIT,
+        remaining,
+        chrono::Utc::now().timestamp() + 3600,
+        body
+    );
+
+    let mut stream = reader.into_inner();
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_github_user() {
+        let github = MockGitHub::start("octocat", "ghp_token").await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{}/user", github.url()))
+            .bearer_auth("ghp_token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "4999");
+        let user: Value = response.json().await.unwrap();
+        assert_eq!(user["login"], "octocat");
+
+        let response = client.get(format!("{}/user", github.url())).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert_eq!(github.requests().len(), 2);
+    }
+}
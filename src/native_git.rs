@@ -0,0 +1,243 @@
+use crate::error::{PMSError, Result};
+use crate::git_backend::{CliBackend, GitBackend, StatusEntry};
+use async_trait::async_trait;
+use git2::{DiffFormat, DiffOptions, IndexAddOption, Oid, Repository, StatusOptions};
+use std::path::PathBuf;
+
+/// Works on the repository through libgit2 instead of spawning `git`.
+///
+/// Fetch, push and all other commands still run `git`, so credential
+/// helpers, SSH configuration and Git LFS keep working.
+pub struct NativeBackend {
+    repo_path: PathBuf,
+    cli: CliBackend,
+}
+
+impl NativeBackend {
+    pub fn new(repo_path: PathBuf) -> Self {
+        Self {
+            cli: CliBackend::new(repo_path.clone()),
+            repo_path,
+        }
+    }
+
+    /// Run `operation` on the repository in a blocking task.
+    async fn with_repo<T, F>(&self, command: &str, operation: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Repository) -> std::result::Result<T, git2::Error> + Send + 'static,
+    {
+        let repo_path = self.repo_path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let repo = Repository::open(&repo_path)?;
+            operation(&repo)
+        })
+        .await?;
+
+        result.map_err(|e| {
+            PMSError::GitError {
+                command: command.to_string(),
+                error: e.message().to_string(),
+            }.into()
+        })
+    }
+}
+
+fn head_tree(repo: &Repository) -> std::result::Result<Option<git2::Tree<'_>>, git2::Error> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_tree()?)),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch || e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn path_string(path: Option<&std::path::Path>) -> Option<String> {
+    path.map(|p| p.to_string_lossy().to_string())
+}
+
+#[async_trait]
+impl GitBackend for NativeBackend {
+    async fn status(&self) -> Result<Vec<StatusEntry>> {
+        self.with_repo("status", |repo| {
+            let mut options = StatusOptions::new();
+            options
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .renames_head_to_index(true);
+
+            let statuses = repo.statuses(Some(&mut options))?;
+            Ok(statuses
+                .iter()
+                .filter_map(|entry| {
+                    let rename = entry
+                        .head_to_index()
+                        .filter(|_| entry.status().is_index_renamed());
+                    let source = rename.and_then(|delta| path_string(delta.old_file().path()));
+                    let path = entry.path()?.to_string();
+                    Some(StatusEntry { path, source })
+                })
+                .collect())
+        })
+        .await
+    }
+
+    async fn add(&self, paths: &[String]) -> Result<()> {
+        // Filters such as Git LFS are external programs only `git` runs
+        let filtered = {
+            let paths = paths.to_vec();
+            self.with_repo("check-attr filter", move |repo| {
+                let mut filtered = Vec::new();
+                for path in paths {
+                    let filter = repo.get_attr(std::path::Path::new(&path), "filter", git2::AttrCheckFlags::FILE_THEN_INDEX)?;
+                    if filter.is_some() {
+                        filtered.push(path);
+                    }
+                }
+                Ok(filtered)
+            })
+            .await?
+        };
+        if !filtered.is_empty() {
+            self.cli.add(&filtered).await?;
+        }
+
+        let paths: Vec<String> = paths.iter().filter(|p| !filtered.contains(p)).cloned().collect();
+        self.with_repo("add", move |repo| {
+            let mut index = repo.index()?;
+            // `update_all` stages deletions, `add_all` new and modified files
+            index.update_all(paths.iter(), None)?;
+            index.add_all(paths.iter(), IndexAddOption::DEFAULT, None)?;
+            index.write()
+        })
+        .await
+    }
+
+    async fn unstage(&self, paths: &[&str]) -> Result<()> {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        self.with_repo("reset", move |repo| {
+            match repo.head() {
+                Ok(head) => {
+                    let commit = head.peel_to_commit()?;
+                    repo.reset_default(Some(commit.as_object()), paths.iter())
+                }
+                // Unborn branches have no HEAD to reset the index to
+                Err(_) => {
+                    let mut index = repo.index()?;
+                    for path in &paths {
+                        index.remove_path(std::path::Path::new(path))?;
+                    }
+                    index.write()
+                }
+            }
+        })
+        .await
+    }
+
+    async fn staged_paths(&self) -> Result<Vec<String>> {
+        self.with_repo("diff --cached", |repo| {
+            let tree = head_tree(repo)?;
+            let diff = repo.diff_tree_to_index(tree.as_ref(), None, None)?;
+            Ok(diff
+                .deltas()
+                .filter_map(|delta| path_string(delta.new_file().path().or(delta.old_file().path())))
+                .collect())
+        })
+        .await
+    }
+
+    async fn staged_diff(&self) -> Result<String> {
+        self.with_repo("diff --cached", |repo| {
+            let tree = head_tree(repo)?;
+            let mut options = DiffOptions::new();
+            options.context_lines(0).old_prefix("a/").new_prefix("b/");
+            let diff = repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut options))?;
+
+            let mut patch = String::new();
+            diff.print(DiffFormat::Patch, |_, _, line| {
+                if matches!(line.origin(), '+' | '-' | ' ') {
+                    patch.push(line.origin());
+                }
+                patch.push_str(&String::from_utf8_lossy(line.content()));
+                true
+            })?;
+            Ok(patch)
+        })
+        .await
+    }
+
+    async fn commit(&self, message: &str) -> Result<()> {
+        let message = message.to_string();
+        self.with_repo("commit", move |repo| {
+            let signature = repo.signature()?;
+            let mut index = repo.index()?;
+            let tree = repo.find_tree(index.write_tree()?)?;
+
+            let parent = match repo.head() {
+                Ok(head) => Some(head.peel_to_commit()?),
+                Err(_) => None,
+            };
+            // Like `git commit`, refuse to record a commit without changes
+            let unchanged = match &parent {
+                Some(parent) => parent.tree_id() == tree.id(),
+                None => tree.is_empty(),
+            };
+            if unchanged {
+                return Err(git2::Error::from_str("nothing to commit"));
+            }
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn resolve(&self, reference: &str) -> Result<Option<String>> {
+        let reference = reference.to_string();
+        self.with_repo("rev-parse", move |repo| match repo.revparse_single(&reference) {
+            Ok(object) => Ok(Some(object.id().to_string())),
+            Err(e) if e.code() == git2::ErrorCode::NotFound || e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+            Err(e) => Err(e),
+        })
+        .await
+    }
+
+    async fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        let (ancestor, descendant) = (ancestor.to_string(), descendant.to_string());
+        self.with_repo("merge-base --is-ancestor", move |repo| {
+            let ancestor = repo.revparse_single(&ancestor)?.peel_to_commit()?.id();
+            let descendant = repo.revparse_single(&descendant)?.peel_to_commit()?.id();
+            Ok(ancestor == descendant || repo.graph_descendant_of(descendant, ancestor)?)
+        })
+        .await
+    }
+
+    async fn count_commits(&self, from: Option<&str>, to: &str) -> Result<usize> {
+        let (from, to) = (from.map(String::from), to.to_string());
+        self.with_repo("rev-list --count", move |repo| {
+            let mut walk = repo.revwalk()?;
+            walk.push(repo.revparse_single(&to)?.peel_to_commit()?.id())?;
+            if let Some(from) = from {
+                walk.hide(repo.revparse_single(&from)?.peel_to_commit()?.id())?;
+            }
+            walk.try_fold(0, |count, oid: std::result::Result<Oid, git2::Error>| oid.map(|_| count + 1))
+        })
+        .await
+    }
+
+    async fn fetch(&self, remote: &str) -> Result<()> {
+        self.cli.fetch(remote).await
+    }
+
+    async fn push(&self, remote: &str, refspec: &str, force: bool) -> Result<()> {
+        self.cli.push(remote, refspec, force).await
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String> {
+        self.cli.run(args).await
+    }
+
+    /// Autosaves go through `git`, which can stage into another index file.
+    fn detached(&self, index: &std::path::Path, reference: &str) -> Box<dyn GitBackend> {
+        self.cli.detached(index, reference)
+    }
+}
@@ -0,0 +1,824 @@
+use async_trait::async_trait;
+use crate::config::{Config, HostingConfig, ProviderKind, RepositoryConfig, Visibility};
+use crate::error::{PMSError, Result};
+use crate::logging;
+use secrecy::{ExposeSecret, Secret};
+use tokio::process::Command;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+const GITHUB_WEB_URL: &str = "https://github.com";
+const GITLAB_URL: &str = "https://gitlab.com";
+
+/// A service that hosts the remote repositories pms pushes to.
+#[async_trait]
+pub trait HostingProvider: Send + Sync {
+    /// Human readable provider name used in log and error messages
+    fn name(&self) -> &'static str;
+
+    /// Create the remote repository, succeeding if it already exists
+    async fn create_repository(&self, name: &str, options: &RepositoryConfig) -> Result<()>;
+
+    /// URL git uses for the `origin` remote; never contains credentials
+    fn remote_url(&self, name: &str) -> String;
+
+    /// URL of the repository page shown to the user
+    fn web_url(&self, name: &str) -> String;
+
+    /// `git@host:owner/name.git` remote for the SSH transport, if the provider has one
+    fn ssh_url(&self, name: &str) -> Option<String> {
+        crate::ssh::ssh_url(&self.web_url(name))
+    }
+
+    async fn repository_exists(&self, name: &str) -> Result<bool>;
+
+    /// Register `public_key` as a deploy key with write access, succeeding if it already is
+    async fn add_deploy_key(&self, name: &str, title: &str, public_key: &str) -> Result<()>;
+
+    #[allow(dead_code)]
+    async fn delete_repository(&self, name: &str) -> Result<()>;
+}
+
+pub fn from_config(config: &Config) -> Box<dyn HostingProvider> {
+    // Repositories live under the organization when one is configured
+    let owner = config
+        .repository
+        .organization
+        .clone()
+        .unwrap_or_else(|| config.git_username.clone());
+    let token = config.get_token().clone();
+    let url = config.hosting.url.clone();
+
+    match config.hosting.provider {
+        ProviderKind::GitHub => Box::new(GitHubProvider::new(
+            github_api_url(&config.hosting),
+            url.unwrap_or_else(|| GITHUB_WEB_URL.to_string()),
+            owner,
+            token,
+        )),
+        ProviderKind::GitLab => Box::new(GitLabProvider::new(
+            url.unwrap_or_else(|| GITLAB_URL.to_string()),
+            owner,
+            token,
+        )),
+        ProviderKind::Gitea => Box::new(GiteaProvider::new(url.unwrap_or_default(), owner, token)),
+        ProviderKind::Git => Box::new(PlainGitProvider::new(
+            config.hosting.remote_url.clone().unwrap_or_default(),
+            owner,
+        )),
+    }
+}
+
+/// Repository page of the remote `url`, which need not follow the current config.
+///
+/// Remotes other than http(s) and SSH, e.g. local paths, are their own page.
+pub fn web_url_of(url: &str) -> String {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return url.trim_end_matches('/').trim_end_matches(".git").to_string();
+    }
+    match crate::ssh::is_ssh_url(url) {
+        true => crate::ssh::web_url(url).unwrap_or_else(|| url.to_string()),
+        false => url.to_string(),
+    }
+}
+
+/// GitHub Enterprise Server serves its API under `/api/v3` of the web host.
+fn github_api_url(hosting: &HostingConfig) -> String {
+    match (&hosting.api_url, &hosting.url) {
+        (Some(api_url), _) => api_url.clone(),
+        (None, Some(url)) => format!("{}/api/v3", url.trim_end_matches('/')),
+        (None, None) => GITHUB_API_URL.to_string(),
+    }
+}
+
+/// Turn an unsuccessful API response into an error, keeping its status.
+async fn api_error(provider: &str, response: reqwest::Response) -> PMSError {
+    let status = response.status();
+    let rate_limited = status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || response
+            .headers()
+            .get("x-ratelimit-remaining")
+            .is_some_and(|remaining| remaining == "0");
+    let error = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+
+    if rate_limited {
+        PMSError::RateLimited {
+            provider: provider.to_string(),
+            error,
+        }
+    } else {
+        PMSError::ApiError {
+            provider: provider.to_string(),
+            status: status.as_u16(),
+            error,
+        }
+    }
+}
+
+fn network_error(e: reqwest::Error) -> PMSError {
+    PMSError::NetworkError(e.to_string())
+}
+
+fn warn_unsupported(provider: &str, option: &str) {
+    logging::warning(&format!("{} does not support setting {} on creation, ignoring it", provider, option));
+}
+
+pub struct GitHubProvider {
+    client: reqwest::Client,
+    api_url: String,
+    web_url: String,
+    owner: String,
+    token: Secret<String>,
+}
+
+impl GitHubProvider {
+    pub fn new(api_url: String, web_url: String, owner: String, token: Secret<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            web_url: web_url.trim_end_matches('/').to_string(),
+            owner,
+            token,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.api_url, path))
+            .header("Authorization", format!("Bearer {}", self.token.expose_secret()))
+            .header("User-Agent", "pms")
+    }
+
+    fn create_body(&self, name: &str, options: &RepositoryConfig) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "name": name,
+            "private": options.visibility != Visibility::Public,
+            "visibility": options.visibility.to_string(),
+            "auto_init": false,
+        });
+        // GitHub commits the templates itself; init then joins that unrelated commit
+        let fields = [
+            ("description", &options.description),
+            ("homepage", &options.homepage),
+            ("license_template", &options.license_template),
+            ("gitignore_template", &options.gitignore_template),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body[key] = serde_json::json!(value);
+            }
+        }
+        body
+    }
+}
+
+#[async_trait]
+impl HostingProvider for GitHubProvider {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    async fn create_repository(&self, name: &str, options: &RepositoryConfig) -> Result<()> {
+        let path = match &options.organization {
+            Some(org) => format!("/orgs/{}/repos", org),
+            None => "/user/repos".to_string(),
+        };
+        let response = self
+            .request(reqwest::Method::POST, &path)
+            .json(&self.create_body(name, options))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            let error = api_error(self.name(), response).await;
+
+            // Ignore if repository already exists
+            if !error.to_string().contains("already exists") {
+                return Err(error.into());
+            }
+        }
+
+        // Topics cannot be given on creation
+        if !options.topics.is_empty() {
+            let response = self
+                .request(reqwest::Method::PUT, &format!("/repos/{}/{}/topics", self.owner, name))
+                .json(&serde_json::json!({ "names": options.topics }))
+                .send()
+                .await
+                .map_err(network_error)?;
+
+            if !response.status().is_success() {
+                return Err(api_error(self.name(), response).await.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remote_url(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.web_url, self.owner, name)
+    }
+
+    fn web_url(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.web_url, self.owner, name)
+    }
+
+    async fn repository_exists(&self, name: &str) -> Result<bool> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/repos/{}/{}", self.owner, name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            _ => Err(api_error(self.name(), response).await.into()),
+        }
+    }
+
+    async fn add_deploy_key(&self, name: &str, title: &str, public_key: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("/repos/{}/{}/keys", self.owner, name))
+            .json(&serde_json::json!({ "title": title, "key": public_key, "read_only": false }))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            let error = api_error(self.name(), response).await;
+
+            // "key is already in use"
+            if !error.to_string().contains("already in use") {
+                return Err(error.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_repository(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("/repos/{}/{}", self.owner, name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(self.name(), response).await.into());
+        }
+
+        Ok(())
+    }
+}
+
+pub struct GitLabProvider {
+    client: reqwest::Client,
+    base_url: String,
+    owner: String,
+    token: Secret<String>,
+}
+
+impl GitLabProvider {
+    pub fn new(base_url: String, owner: String, token: Secret<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            owner,
+            token,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}/api/v4{}", self.base_url, path))
+            .header("PRIVATE-TOKEN", self.token.expose_secret())
+            .header("User-Agent", "pms")
+    }
+
+    fn project_path(&self, name: &str) -> String {
+        // GitLab addresses projects by their URL-encoded full path
+        format!("/projects/{}%2F{}", self.owner.replace('/', "%2F"), name)
+    }
+
+    fn create_body(&self, name: &str, options: &RepositoryConfig) -> serde_json::Value {
+        for (option, set) in [
+            ("a homepage", options.homepage.is_some()),
+            ("a license template", options.license_template.is_some()),
+            ("a gitignore template", options.gitignore_template.is_some()),
+        ] {
+            if set {
+                warn_unsupported(self.name(), option);
+            }
+        }
+
+        let mut body = serde_json::json!({
+            "name": name,
+            "path": name,
+            "visibility": options.visibility.to_string(),
+        });
+        if let Some(description) = &options.description {
+            body["description"] = serde_json::json!(description);
+        }
+        if !options.topics.is_empty() {
+            body["topics"] = serde_json::json!(options.topics);
+        }
+        body
+    }
+
+    /// Numeric id of the group new projects are created in
+    async fn namespace_id(&self, group: &str) -> Result<u64> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/namespaces/{}", group.replace('/', "%2F")))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(self.name(), response).await.into());
+        }
+
+        let namespace: serde_json::Value = response.json().await.map_err(network_error)?;
+        namespace["id"]
+            .as_u64()
+            .ok_or_else(|| self.error(format!("Group {} has no id", group)).into())
+    }
+
+    fn error(&self, message: String) -> PMSError {
+        PMSError::ProviderError {
+            provider: self.name().to_string(),
+            error: message,
+        }
+    }
+}
+
+#[async_trait]
+impl HostingProvider for GitLabProvider {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    async fn create_repository(&self, name: &str, options: &RepositoryConfig) -> Result<()> {
+        let mut body = self.create_body(name, options);
+        if let Some(group) = &options.organization {
+            body["namespace_id"] = serde_json::json!(self.namespace_id(group).await?);
+        }
+
+        let response = self
+            .request(reqwest::Method::POST, "/projects")
+            .json(&body)
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            let error = api_error(self.name(), response).await;
+
+            // Ignore if repository already exists
+            if !error.to_string().contains("has already been taken") {
+                return Err(error.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remote_url(&self, name: &str) -> String {
+        format!("{}/{}/{}.git", self.base_url, self.owner, name)
+    }
+
+    fn web_url(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.base_url, self.owner, name)
+    }
+
+    async fn repository_exists(&self, name: &str) -> Result<bool> {
+        let response = self
+            .request(reqwest::Method::GET, &self.project_path(name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            _ => Err(api_error(self.name(), response).await.into()),
+        }
+    }
+
+    async fn add_deploy_key(&self, name: &str, title: &str, public_key: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("{}/deploy_keys", self.project_path(name)))
+            .json(&serde_json::json!({ "title": title, "key": public_key, "can_push": true }))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            let error = api_error(self.name(), response).await;
+
+            // The fingerprint "has already been taken"
+            if !error.to_string().contains("has already been taken") {
+                return Err(error.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_repository(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &self.project_path(name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(self.name(), response).await.into());
+        }
+
+        Ok(())
+    }
+}
+
+pub struct GiteaProvider {
+    client: reqwest::Client,
+    base_url: String,
+    owner: String,
+    token: Secret<String>,
+}
+
+impl GiteaProvider {
+    pub fn new(base_url: String, owner: String, token: Secret<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            owner,
+            token,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}/api/v1{}", self.base_url, path))
+            .header("Authorization", format!("token {}", self.token.expose_secret()))
+            .header("User-Agent", "pms")
+    }
+
+    fn create_body(&self, name: &str, options: &RepositoryConfig) -> Result<serde_json::Value> {
+        if options.visibility == Visibility::Internal {
+            return Err(self.error("Internal visibility is not supported, use public or private".to_string()).into());
+        }
+
+        // Templates are only applied when Gitea creates the initial commit
+        let templated = options.license_template.is_some() || options.gitignore_template.is_some();
+        let mut body = serde_json::json!({
+            "name": name,
+            "private": options.visibility == Visibility::Private,
+            "auto_init": templated,
+        });
+        let fields = [
+            ("description", &options.description),
+            ("website", &options.homepage),
+            ("license", &options.license_template),
+            ("gitignores", &options.gitignore_template),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body[key] = serde_json::json!(value);
+            }
+        }
+        Ok(body)
+    }
+
+    fn error(&self, message: String) -> PMSError {
+        PMSError::ProviderError {
+            provider: self.name().to_string(),
+            error: message,
+        }
+    }
+}
+
+#[async_trait]
+impl HostingProvider for GiteaProvider {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    async fn create_repository(&self, name: &str, options: &RepositoryConfig) -> Result<()> {
+        let path = match &options.organization {
+            Some(org) => format!("/orgs/{}/repos", org),
+            None => "/user/repos".to_string(),
+        };
+        let response = self
+            .request(reqwest::Method::POST, &path)
+            .json(&self.create_body(name, options)?)
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        // Gitea answers 409 Conflict when the repository already exists
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::CONFLICT {
+            return Err(api_error(self.name(), response).await.into());
+        }
+
+        if !options.topics.is_empty() {
+            let response = self
+                .request(reqwest::Method::PUT, &format!("/repos/{}/{}/topics", self.owner, name))
+                .json(&serde_json::json!({ "topics": options.topics }))
+                .send()
+                .await
+                .map_err(network_error)?;
+
+            if !response.status().is_success() {
+                return Err(api_error(self.name(), response).await.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remote_url(&self, name: &str) -> String {
+        format!("{}/{}/{}.git", self.base_url, self.owner, name)
+    }
+
+    fn web_url(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.base_url, self.owner, name)
+    }
+
+    async fn repository_exists(&self, name: &str) -> Result<bool> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/repos/{}/{}", self.owner, name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            _ => Err(api_error(self.name(), response).await.into()),
+        }
+    }
+
+    async fn add_deploy_key(&self, name: &str, title: &str, public_key: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::POST, &format!("/repos/{}/{}/keys", self.owner, name))
+            .json(&serde_json::json!({ "title": title, "key": public_key, "read_only": false }))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            let error = api_error(self.name(), response).await;
+
+            // "Key content has been used as non-deploy key" or "... already exists"
+            let message = error.to_string();
+            if !message.contains("has been used") && !message.contains("already exists") {
+                return Err(error.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_repository(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("/repos/{}/{}", self.owner, name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        if !response.status().is_success() {
+            return Err(api_error(self.name(), response).await.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A plain git remote without any hosting API; the repository must already exist.
+pub struct PlainGitProvider {
+    remote_url: String,
+    owner: String,
+}
+
+impl PlainGitProvider {
+    pub fn new(remote_url: String, owner: String) -> Self {
+        Self { remote_url, owner }
+    }
+}
+
+#[async_trait]
+impl HostingProvider for PlainGitProvider {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    async fn create_repository(&self, _name: &str, options: &RepositoryConfig) -> Result<()> {
+        if options != &RepositoryConfig::default() {
+            logging::warning("Repository options are ignored for plain git remotes");
+        }
+        Ok(())
+    }
+
+    fn remote_url(&self, name: &str) -> String {
+        self.remote_url
+            .replace("{owner}", &self.owner)
+            .replace("{name}", name)
+    }
+
+    fn web_url(&self, name: &str) -> String {
+        self.remote_url(name)
+    }
+
+    /// `remote_url` decides the transport, it may be an SSH URL itself
+    fn ssh_url(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    async fn repository_exists(&self, name: &str) -> Result<bool> {
+        let status = Command::new("git")
+            .args(["ls-remote", "--exit-code", &self.remote_url(name)])
+            .output()
+            .await
+            .map_err(|e| PMSError::NetworkError(e.to_string()))?
+            .status;
+
+        // `--exit-code` returns 2 for a reachable but empty repository
+        Ok(status.success() || status.code() == Some(2))
+    }
+
+    async fn add_deploy_key(&self, _name: &str, _title: &str, _public_key: &str) -> Result<()> {
+        Err(PMSError::ProviderError {
+            provider: self.name().to_string(),
+            error: "Deploy keys are not supported for plain git remotes, add the key on the server".to_string(),
+        }.into())
+    }
+
+    async fn delete_repository(&self, _name: &str) -> Result<()> {
+        Err(PMSError::ProviderError {
+            provider: self.name().to_string(),
+            error: "Deleting repositories is not supported for plain git remotes".to_string(),
+        }.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn token() -> Secret<String> {
+        Secret::new("secret".to_string())
+    }
+
+    #[test]
+    fn test_remote_urls() {
+        let mut config = Config::default();
+        config.git_username = "user".to_string();
+        let github = from_config(&config);
+        assert_eq!(github.remote_url("repo"), "https://github.com/user/repo");
+        assert_eq!(github.web_url("repo"), "https://github.com/user/repo");
+        assert_eq!(github_api_url(&config.hosting), GITHUB_API_URL);
+
+        // GitHub Enterprise Server
+        config.hosting.url = Some("https://github.example.com/".to_string());
+        let enterprise = from_config(&config);
+        assert_eq!(enterprise.remote_url("repo"), "https://github.example.com/user/repo");
+        assert_eq!(enterprise.web_url("repo"), "https://github.example.com/user/repo");
+        assert_eq!(github_api_url(&config.hosting), "https://github.example.com/api/v3");
+        config.hosting.api_url = Some("https://api.github.example.com".to_string());
+        assert_eq!(github_api_url(&config.hosting), "https://api.github.example.com");
+
+        let gitlab = GitLabProvider::new("https://gitlab.example.com/".to_string(), "user".to_string(), token());
+        assert_eq!(gitlab.remote_url("repo"), "https://gitlab.example.com/user/repo.git");
+        assert_eq!(gitlab.project_path("repo"), "/projects/user%2Frepo");
+
+        let gitea = GiteaProvider::new("https://gitea.example.com".to_string(), "user".to_string(), token());
+        assert_eq!(gitea.remote_url("repo"), "https://gitea.example.com/user/repo.git");
+        assert_eq!(gitea.web_url("repo"), "https://gitea.example.com/user/repo");
+
+        let plain = PlainGitProvider::new("git@example.com:{owner}/{name}.git".to_string(), "user".to_string());
+        assert_eq!(plain.remote_url("repo"), "git@example.com:user/repo.git");
+
+        // Pages of remotes that were renamed or set up elsewhere
+        assert_eq!(web_url_of("https://gitlab.example.com/team/notes.git"), "https://gitlab.example.com/team/notes");
+        assert_eq!(web_url_of("https://github.com/user/repo"), "https://github.com/user/repo");
+        assert_eq!(web_url_of("git@github.com:user/repo.git"), "https://github.com/user/repo");
+        assert_eq!(web_url_of("/srv/git/notes.git"), "/srv/git/notes.git");
+    }
+
+    #[test]
+    fn test_create_bodies() {
+        let options = RepositoryConfig {
+            visibility: Visibility::Internal,
+            organization: Some("acme".to_string()),
+            description: Some("Team notes".to_string()),
+            homepage: Some("https://acme.example.com".to_string()),
+            topics: vec!["notes".to_string()],
+            license_template: Some("mit".to_string()),
+            gitignore_template: None,
+            branch: None,
+        };
+
+        let github = GitHubProvider::new(GITHUB_API_URL.to_string(), GITHUB_WEB_URL.to_string(), "acme".to_string(), token())
+            .create_body("repo", &options);
+        assert_eq!(github["private"], true);
+        assert_eq!(github["visibility"], "internal");
+        assert_eq!(github["homepage"], "https://acme.example.com");
+        assert_eq!(github["license_template"], "mit");
+        assert!(github.get("gitignore_template").is_none());
+
+        let gitlab = GitLabProvider::new(GITLAB_URL.to_string(), "acme".to_string(), token())
+            .create_body("repo", &options);
+        assert_eq!(gitlab["visibility"], "internal");
+        assert_eq!(gitlab["topics"], serde_json::json!(["notes"]));
+        assert!(gitlab.get("homepage").is_none());
+
+        let gitea = GiteaProvider::new("https://gitea.example.com".to_string(), "acme".to_string(), token());
+        assert!(gitea.create_body("repo", &options).is_err());
+        let public = RepositoryConfig { visibility: Visibility::Public, ..options };
+        let body = gitea.create_body("repo", &public).unwrap();
+        assert_eq!(body["private"], false);
+        assert_eq!(body["auto_init"], true);
+        assert_eq!(body["website"], "https://acme.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_github_api() {
+        use crate::error::ErrorKind;
+        use crate::mock_github::MockGitHub;
+
+        let github = MockGitHub::start("user", "secret").await;
+        let provider = GitHubProvider::new(format!("{}/", github.url()), GITHUB_WEB_URL.to_string(), "user".to_string(), token());
+        let options = RepositoryConfig {
+            description: Some("Notes".to_string()),
+            topics: vec!["notes".to_string()],
+            ..RepositoryConfig::default()
+        };
+
+        assert!(!provider.repository_exists("notes").await.unwrap());
+        provider.create_repository("notes", &options).await.unwrap();
+        assert!(provider.repository_exists("notes").await.unwrap());
+        let repository = github.repository("user", "notes").unwrap();
+        assert_eq!(repository["private"], true);
+        assert_eq!(repository["description"], "Notes");
+        assert_eq!(repository["topics"], serde_json::json!(["notes"]));
+
+        // "name already exists" counts as created
+        provider.create_repository("notes", &RepositoryConfig::default()).await.unwrap();
+
+        let organization = RepositoryConfig {
+            organization: Some("acme".to_string()),
+            ..RepositoryConfig::default()
+        };
+        provider.create_repository("shared", &organization).await.unwrap();
+        assert!(github.repository("acme", "shared").is_some());
+        assert_eq!(github.requests().last().unwrap().path, "/orgs/acme/repos");
+
+        // A key registered before is accepted again
+        let key = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAA pms";
+        provider.add_deploy_key("notes", "pms@laptop", key).await.unwrap();
+        provider.add_deploy_key("notes", "pms@laptop", key).await.unwrap();
+        let keys = github.repository("user", "notes").unwrap()["keys"].clone();
+        assert_eq!(keys.as_array().unwrap().len(), 1);
+        assert_eq!(keys[0]["read_only"], false);
+
+        provider.delete_repository("notes").await.unwrap();
+        assert!(!provider.repository_exists("notes").await.unwrap());
+
+        github.set_rate_limit_remaining(0);
+        let error = provider.repository_exists("notes").await.unwrap_err();
+        assert_eq!(ErrorKind::of(&error), ErrorKind::RateLimit);
+
+        github.set_rate_limit_remaining(10);
+        let unauthorized = GitHubProvider::new(
+            github.url().to_string(),
+            GITHUB_WEB_URL.to_string(),
+            "user".to_string(),
+            Secret::new("wrong".to_string()),
+        );
+        let error = unauthorized.repository_exists("notes").await.unwrap_err();
+        assert_eq!(ErrorKind::of(&error), ErrorKind::Auth);
+    }
+
+    #[tokio::test]
+    async fn test_plain_git_repository_exists() {
+        let temp_dir = tempdir().unwrap();
+        let bare = temp_dir.path().join("repo.git");
+        Command::new("git")
+            .args(["init", "--bare", bare.to_str().unwrap()])
+            .output()
+            .await
+            .unwrap();
+
+        let template = temp_dir.path().join("{name}.git");
+        let provider = PlainGitProvider::new(template.display().to_string(), "user".to_string());
+
+        assert!(provider.repository_exists("repo").await.unwrap());
+        assert!(!provider.repository_exists("missing").await.unwrap());
+        assert!(provider.delete_repository("repo").await.is_err());
+    }
+}
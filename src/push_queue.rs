@@ -0,0 +1,145 @@
+use crate::backoff::Backoff;
+use crate::error::ErrorKind;
+use crate::logging;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// First retry delay after a failed push
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest wait between push attempts, also used for authentication failures
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Name of the state file, resolved with `git rev-parse --git-path` so
+/// worktrees and submodules, where `.git` is a file, work as well
+pub const STATE_FILE: &str = "pms-push.toml";
+
+/// Failed pushes of a repository, kept across restarts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PushState {
+    pub failures: u32,
+    pub since: Option<DateTime<Local>>,
+    pub kind: Option<ErrorKind>,
+    pub last_error: Option<String>,
+}
+
+/// Schedules retries for commits that were made locally but not pushed.
+///
+/// The commits themselves stay in the repository; the queue only remembers
+/// how pushing them went, in `pms-push.toml` in the git directory.
+pub struct PushQueue {
+    state_path: PathBuf,
+    state: PushState,
+    backoff: Backoff,
+    next_attempt: Option<Instant>,
+}
+
+impl PushQueue {
+    /// Load the state kept at `state_path`, see [`STATE_FILE`].
+    pub fn load(state_path: PathBuf) -> Self {
+        let state: PushState = fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default();
+
+        // Continue the backoff where the previous run left off
+        let mut backoff = Backoff::new(RETRY_DELAY, MAX_RETRY_DELAY).with_jitter(0.2);
+        for _ in 0..state.failures.min(16) {
+            backoff.next_delay();
+        }
+
+        Self {
+            state_path,
+            state,
+            backoff,
+            next_attempt: None,
+        }
+    }
+
+    pub fn state(&self) -> &PushState {
+        &self.state
+    }
+
+    /// Whether the retry delay after the last failure has passed.
+    pub fn is_due(&self) -> bool {
+        self.next_attempt.is_none_or(|next| Instant::now() >= next)
+    }
+
+    pub fn record_success(&mut self) {
+        self.backoff.reset();
+        self.next_attempt = None;
+        if self.state != PushState::default() {
+            self.state = PushState::default();
+            let _ = fs::remove_file(&self.state_path);
+        }
+    }
+
+    /// Remember a failed push and return how long to wait before the next one.
+    pub fn record_failure(&mut self, error: &anyhow::Error) -> Duration {
+        // Retrying soon will not fix credentials
+        let kind = ErrorKind::of(error);
+        let delay = match kind {
+            ErrorKind::Auth => MAX_RETRY_DELAY,
+            _ => self.backoff.next_delay(),
+        };
+
+        self.state.failures += 1;
+        self.state.since.get_or_insert_with(Local::now);
+        self.state.kind = Some(kind);
+        self.state.last_error = Some(format!("{:#}", error));
+        self.next_attempt = Some(Instant::now() + delay);
+        self.save();
+
+        delay
+    }
+
+    fn save(&self) {
+        let result = toml::to_string(&self.state)
+            .map_err(|e| e.to_string())
+            .and_then(|content| fs::write(&self.state_path, content).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            logging::warning(&format!("Failed to save push state: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PMSError;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_push_state_persists() {
+        let temp_dir = tempdir().unwrap();
+        let state_path = temp_dir.path().join(STATE_FILE);
+
+        let mut queue = PushQueue::load(state_path.clone());
+        assert!(queue.is_due());
+
+        let git_error = |error: &str| {
+            anyhow::Error::from(PMSError::GitError {
+                command: "push origin HEAD:refs/heads/main".to_string(),
+                error: error.to_string(),
+            })
+        };
+        let offline = git_error("Could not resolve host: example.com");
+        let first = queue.record_failure(&offline);
+        let second = queue.record_failure(&offline);
+        assert!(second > first);
+        assert!(!queue.is_due());
+        assert_eq!(queue.record_failure(&git_error("Authentication failed")), MAX_RETRY_DELAY);
+
+        let reloaded = PushQueue::load(state_path.clone());
+        assert_eq!(reloaded.state().failures, 3);
+        assert_eq!(reloaded.state().kind, Some(ErrorKind::Auth));
+        // A restart retries right away
+        assert!(reloaded.is_due());
+
+        queue.record_success();
+        assert_eq!(PushQueue::load(state_path.clone()).state(), &PushState::default());
+    }
+}
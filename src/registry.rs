@@ -0,0 +1,311 @@
+use crate::config::{self, ProviderKind};
+use crate::error::{PMSError, Result};
+use crate::watcher::WatcherStatus;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+
+/// A project pms keeps in sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub path: PathBuf,
+    pub remote: Option<String>,
+    #[serde(default)]
+    pub provider: ProviderKind,
+    pub created: DateTime<Local>,
+    pub last_sync: Option<SyncRecord>,
+}
+
+impl Project {
+    pub fn new(name: &str, path: &Path, provider: ProviderKind) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            remote: None,
+            provider,
+            created: Local::now(),
+            last_sync: None,
+        }
+    }
+}
+
+/// Outcome of the most recent sync of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub time: DateTime<Local>,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Projects known to pms, stored next to the config file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(skip)]
+    registry_path: PathBuf,
+
+    #[serde(default, rename = "project")]
+    pub projects: Vec<Project>,
+}
+
+impl Registry {
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path()?)
+    }
+
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(config::config_dir()?.join("projects.toml"))
+    }
+
+    /// Read the registry at `path`, starting empty when it does not exist yet.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let mut registry = if path.exists() {
+            let content = fs::read_to_string(path).map_err(|e| {
+                PMSError::ConfigError(format!("Failed to read project registry: {}", e))
+            })?;
+            toml::from_str(&content).map_err(|e| {
+                PMSError::ConfigError(format!("Invalid project registry format: {}", e))
+            })?
+        } else {
+            Registry::default()
+        };
+
+        registry.registry_path = path.to_path_buf();
+        Ok(registry)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = toml::to_string(self).map_err(|e| {
+            PMSError::ConfigError(format!("Failed to serialize project registry: {}", e))
+        })?;
+
+        if let Some(parent) = self.registry_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                PMSError::ConfigError(format!("Failed to create config directory: {}", e))
+            })?;
+        }
+
+        // Write to a temporary file first so readers never see half a registry
+        let temp_path = self.registry_path.with_extension("toml.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|_| fs::rename(&temp_path, &self.registry_path))
+            .map_err(|e| PMSError::ConfigError(format!("Failed to write project registry: {}", e)))?;
+        Ok(())
+    }
+
+    /// Load the registry at `path`, apply `update` and save it again.
+    ///
+    /// `pms watch`, the daemon and the CLI update the registry from separate
+    /// processes, so the cycle holds a lock on `projects.toml.lock`.
+    pub fn update<T>(path: &Path, update: impl FnOnce(&mut Registry) -> Result<T>) -> Result<T> {
+        let lock_error = |e: std::io::Error| PMSError::ConfigError(format!("Failed to lock project registry: {}", e));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(lock_error)?;
+        }
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("toml.lock"))
+            .map_err(lock_error)?;
+        lock.lock().map_err(lock_error)?;
+
+        let mut registry = Self::load_from(path)?;
+        let value = update(&mut registry)?;
+        registry.save()?;
+        Ok(value)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Project> {
+        self.projects.iter().find(|p| p.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<&mut Project> {
+        self.projects
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| PMSError::InvalidConfig(format!("Unknown project: {}", name)).into())
+    }
+
+    pub fn find_by_path(&self, path: &Path) -> Option<&Project> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.projects.iter().find(|p| p.path == path)
+    }
+
+    /// Register `project`, refusing duplicate names and directories.
+    pub fn add(&mut self, mut project: Project) -> Result<()> {
+        if project.name.trim().is_empty() {
+            return Err(PMSError::InvalidConfig("Project name cannot be empty".to_string()).into());
+        }
+        project.path = project.path.canonicalize().map_err(|e| {
+            PMSError::InvalidPath(format!("{}: {}", project.path.display(), e))
+        })?;
+
+        if self.get(&project.name).is_some() {
+            return Err(PMSError::InvalidConfig(format!(
+                "A project named {} is already registered",
+                project.name
+            )).into());
+        }
+        if let Some(existing) = self.find_by_path(&project.path) {
+            return Err(PMSError::InvalidConfig(format!(
+                "{} is already registered as {}",
+                project.path.display(),
+                existing.name
+            )).into());
+        }
+
+        self.projects.push(project);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Project> {
+        let index = self
+            .projects
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| PMSError::InvalidConfig(format!("Unknown project: {}", name)))?;
+        Ok(self.projects.remove(index))
+    }
+
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<()> {
+        if new_name.trim().is_empty() {
+            return Err(PMSError::InvalidConfig("Project name cannot be empty".to_string()).into());
+        }
+        if self.get(new_name).is_some() {
+            return Err(PMSError::InvalidConfig(format!(
+                "A project named {} is already registered",
+                new_name
+            )).into());
+        }
+
+        self.get_mut(name)?.name = new_name.to_string();
+        Ok(())
+    }
+
+    pub fn record_sync(&mut self, name: &str, record: SyncRecord) -> Result<()> {
+        self.get_mut(name)?.last_sync = Some(record);
+        Ok(())
+    }
+}
+
+/// Store the sync outcomes a watcher publishes as the project's last-sync status.
+///
+/// Runs until the watcher goes away. Projects that are not registered are ignored.
+pub async fn record_syncs(name: String, mut status: watch::Receiver<WatcherStatus>) {
+    let Ok(path) = Registry::default_path() else {
+        return;
+    };
+    let mut previous = status.borrow().clone();
+
+    while status.changed().await.is_ok() {
+        let current = status.borrow_and_update().clone();
+        let record = if current.last_sync != previous.last_sync {
+            current.last_sync.map(|time| SyncRecord {
+                time,
+                ok: true,
+                error: None,
+            })
+        } else if current.last_error.is_some() && current.last_error != previous.last_error {
+            Some(SyncRecord {
+                time: Local::now(),
+                ok: false,
+                error: current.last_error.clone(),
+            })
+        } else {
+            None
+        };
+        previous = current;
+
+        let Some(record) = record else {
+            continue;
+        };
+        if Registry::load_from(&path).is_ok_and(|registry| registry.get(&name).is_some()) {
+            if let Err(e) = Registry::update(&path, |registry| registry.record_sync(&name, record)) {
+                crate::logging::warning(&format!("Failed to record sync status of {}: {}", name, e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_registry_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("projects.toml");
+
+        let mut registry = Registry::load_from(&path).unwrap();
+        assert!(registry.projects.is_empty());
+
+        registry.add(Project::new("notes", temp_dir.path(), ProviderKind::GitLab)).unwrap();
+        registry.record_sync("notes", SyncRecord {
+            time: Local::now(),
+            ok: false,
+            error: Some("network unreachable".to_string()),
+        }).unwrap();
+        registry.save().unwrap();
+
+        let registry = Registry::load_from(&path).unwrap();
+        let project = registry.get("notes").unwrap();
+        assert_eq!(project.path, temp_dir.path().canonicalize().unwrap());
+        assert_eq!(project.provider, ProviderKind::GitLab);
+        assert_eq!(project.last_sync.as_ref().unwrap().error.as_deref(), Some("network unreachable"));
+    }
+
+    #[test]
+    fn test_registry_changes() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("projects.toml");
+        fs::create_dir(temp_dir.path().join("a")).unwrap();
+        fs::create_dir(temp_dir.path().join("b")).unwrap();
+
+        Registry::update(&path, |registry| {
+            registry.add(Project::new("a", &temp_dir.path().join("a"), ProviderKind::GitHub))?;
+            registry.add(Project::new("b", &temp_dir.path().join("b"), ProviderKind::GitHub))
+        }).unwrap();
+
+        let mut registry = Registry::load_from(&path).unwrap();
+        // Names and directories are unique
+        assert!(registry.add(Project::new("a", temp_dir.path(), ProviderKind::GitHub)).is_err());
+        assert!(registry.add(Project::new("c", &temp_dir.path().join("a"), ProviderKind::GitHub)).is_err());
+        assert!(registry.add(Project::new("d", &temp_dir.path().join("missing"), ProviderKind::GitHub)).is_err());
+        assert!(registry.rename("a", "b").is_err());
+
+        registry.rename("a", "alpha").unwrap();
+        assert_eq!(registry.find_by_path(&temp_dir.path().join("a")).unwrap().name, "alpha");
+        assert_eq!(registry.remove("b").unwrap().name, "b");
+        assert!(registry.remove("b").is_err());
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("projects.toml");
+
+        // Each thread opens the lock file itself, like separate processes do
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = temp_dir.path().join(format!("p{}", i));
+                fs::create_dir(&dir).unwrap();
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    Registry::update(&path, |registry| {
+                        registry.add(Project::new(&format!("p{}", i), &dir, ProviderKind::GitHub))
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(Registry::load_from(&path).unwrap().projects.len(), 8);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::config;
+use crate::error::{PMSError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Whether git reaches `url` over SSH, e.g. `git@host:owner/repo.git` or `ssh://host/repo`.
+pub fn is_ssh_url(url: &str) -> bool {
+    if url.starts_with("ssh://") {
+        return true;
+    }
+    // scp-like syntax: a colon before the first slash and no scheme
+    match url.split_once(':') {
+        Some((host, _)) => !host.is_empty() && !host.contains('/') && !url.contains("://"),
+        None => false,
+    }
+}
+
+/// `git@host:owner/repo.git` for a repository page like `https://host/owner/repo`.
+///
+/// A port in the web URL is dropped, SSH servers listen on their own port.
+pub fn ssh_url(web_url: &str) -> Option<String> {
+    let host = crate::credential::url_host(web_url)?;
+    let host = host.split(':').next()?;
+    let (_, path) = web_url.split_once("://")?.1.split_once('/')?;
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        return None;
+    }
+    Some(format!("git@{}:{}.git", host, path))
+}
+
+/// `https://host/owner/repo` for an SSH remote like `git@host:owner/repo.git`.
+pub fn web_url(ssh_url: &str) -> Option<String> {
+    let (authority, path) = match ssh_url.strip_prefix("ssh://") {
+        Some(rest) => rest.split_once('/')?,
+        None => ssh_url.split_once(':')?,
+    };
+    let host = authority.rsplit_once('@').map(|(_, h)| h).unwrap_or(authority);
+    let host = host.split(':').next()?;
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(format!("https://{}/{}", host, path))
+}
+
+/// Directory holding the deploy keys pms created
+pub fn keys_dir() -> Result<PathBuf> {
+    Ok(config::config_dir()?.join("keys"))
+}
+
+/// Private key file of the deploy key for the repository `name`
+pub fn deploy_key_path(keys_dir: &Path, name: &str) -> PathBuf {
+    keys_dir.join(format!("{}_ed25519", name))
+}
+
+/// Create an ed25519 key pair without passphrase at `path` unless it exists,
+/// and return the public key.
+pub async fn ensure_key(path: &Path, comment: &str) -> Result<String> {
+    let public_path = path.with_extension("pub");
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let output = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", comment, "-f"])
+            .arg(path)
+            .output()
+            .await
+            .map_err(|e| PMSError::SshKeyError(format!("Failed to run ssh-keygen: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(PMSError::SshKeyError(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ).into());
+        }
+    }
+
+    let public_key = fs::read_to_string(&public_path)
+        .map_err(|e| PMSError::SshKeyError(format!("{}: {}", public_path.display(), e)))?;
+    Ok(public_key.trim().to_string())
+}
+
+/// `core.sshCommand` that authenticates with only the key at `path`
+pub fn ssh_command(path: &Path) -> String {
+    format!("ssh -i '{}' -o IdentitiesOnly=yes", path.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ssh_urls() {
+        assert!(is_ssh_url("git@github.com:user/repo.git"));
+        assert!(is_ssh_url("ssh://git@example.com:2222/user/repo.git"));
+        assert!(!is_ssh_url("https://github.com/user/repo"));
+        assert!(!is_ssh_url("/srv/git/repo.git"));
+
+        assert_eq!(ssh_url("https://github.com/user/repo").as_deref(), Some("git@github.com:user/repo.git"));
+        assert_eq!(
+            ssh_url("https://gitea.example.com:3000/team/notes/").as_deref(),
+            Some("git@gitea.example.com:team/notes.git")
+        );
+        assert_eq!(ssh_url("https://github.com"), None);
+
+        assert_eq!(web_url("git@github.com:user/repo.git").as_deref(), Some("https://github.com/user/repo"));
+        assert_eq!(
+            web_url("ssh://git@example.com:2222/team/notes.git").as_deref(),
+            Some("https://example.com/team/notes")
+        );
+        assert_eq!(web_url("git@github.com:"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_key() {
+        let temp_dir = tempdir().unwrap();
+        let path = deploy_key_path(&temp_dir.path().join("keys"), "notes");
+
+        let public_key = ensure_key(&path, "pms deploy key for notes").await.unwrap();
+        assert!(public_key.starts_with("ssh-ed25519 "));
+        assert!(public_key.ends_with("pms deploy key for notes"));
+
+        // An existing key is kept
+        assert_eq!(ensure_key(&path, "other").await.unwrap(), public_key);
+        assert_eq!(ssh_command(&path), format!("ssh -i '{}' -o IdentitiesOnly=yes", path.display()));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::config::{self, Config};
+use crate::error::{PMSError, Result};
+use chrono::{Datelike, Local};
+use ignore::WalkBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A template compiled into pms, as (path, content) pairs
+pub struct BuiltIn {
+    pub name: &'static str,
+    pub description: &'static str,
+    files: &'static [(&'static str, &'static str)],
+}
+
+const GITIGNORE_COMMON: &str = ".DS_Store\nThumbs.db\n*.swp\n";
+
+pub const BUILT_INS: [BuiltIn; 4] = [
+    BuiltIn {
+        name: "basic",
+        description: "README and a .gitignore for editor and OS files",
+        files: &[
+            ("README.md", "# {{project_name}}\n\nCreated by {{author}} <{{email}}>.\n"),
+            (".gitignore", GITIGNORE_COMMON),
+        ],
+    },
+    BuiltIn {
+        name: "rust-lib",
+        description: "Rust library crate",
+        files: &[
+            ("README.md", "# {{project_name}}\n\n```toml\n[dependencies]\n{{crate_name}} = \"0.1\"\n```\n"),
+            (".gitignore", "/target\n"),
+            (
+                "Cargo.toml",
+                "[package]\nname = \"{{crate_name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\nauthors = [\"{{author}} <{{email}}>\"]\n\n[dependencies]\n",
+            ),
+            (
+                "src/lib.rs",
+                "pub fn add(left: u64, right: u64) -> u64 {\n    left + right\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn it_works() {\n        assert_eq!(add(2, 2), 4);\n    }\n}\n",
+            ),
+            ("LICENSE", "Copyright (c) {{year}} {{author}}\n"),
+        ],
+    },
+    BuiltIn {
+        name: "rust-bin",
+        description: "Rust command line application",
+        files: &[
+            ("README.md", "# {{project_name}}\n\n```bash\ncargo run\n```\n"),
+            (".gitignore", "/target\n"),
+            (
+                "Cargo.toml",
+                "[package]\nname = \"{{crate_name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\nauthors = [\"{{author}} <{{email}}>\"]\n\n[dependencies]\n",
+            ),
+            ("src/main.rs", "fn main() {\n    println!(\"Hello from {{project_name}}!\");\n}\n"),
+            ("LICENSE", "Copyright (c) {{year}} {{author}}\n"),
+        ],
+    },
+    BuiltIn {
+        name: "python",
+        description: "Python package with a pyproject.toml",
+        files: &[
+            ("README.md", "# {{project_name}}\n\n```bash\npip install -e .\n```\n"),
+            (".gitignore", "__pycache__/\n*.pyc\n.venv/\ndist/\n*.egg-info/\n"),
+            (
+                "pyproject.toml",
+                "[project]\nname = \"{{project_name}}\"\nversion = \"0.1.0\"\nauthors = [{ name = \"{{author}}\", email = \"{{email}}\" }]\n\n[build-system]\nrequires = [\"setuptools>=61\"]\nbuild-backend = \"setuptools.build_meta\"\n",
+            ),
+            ("src/{{crate_name}}/__init__.py", "\"\"\"{{project_name}}\"\"\"\n"),
+        ],
+    },
+];
+
+/// Values substituted for `{{placeholder}}` in template file names and contents.
+pub struct Placeholders {
+    values: Vec<(&'static str, String)>,
+}
+
+impl Placeholders {
+    pub fn new(project_name: &str, config: &Config) -> Self {
+        Self {
+            values: vec![
+                ("project_name", project_name.to_string()),
+                ("crate_name", project_name.replace(['-', '.'], "_").to_lowercase()),
+                ("author", config.git_username.clone()),
+                ("email", config.git_email.clone()),
+                ("year", Local::now().year().to_string()),
+            ],
+        }
+    }
+
+    pub fn substitute(&self, text: &str) -> String {
+        self.values.iter().fold(text.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{{{}}}}}", key), value)
+        })
+    }
+}
+
+/// Files a new project starts with.
+#[derive(Debug, Clone)]
+pub enum Template {
+    BuiltIn(&'static str),
+    Directory(PathBuf),
+}
+
+impl Template {
+    /// Write the template into `target`, keeping files that already exist.
+    ///
+    /// Returns the files that were created.
+    pub fn apply(&self, target: &Path, placeholders: &Placeholders) -> Result<Vec<PathBuf>> {
+        let mut created = Vec::new();
+
+        for (relative, content) in self.files()? {
+            let path = target.join(placeholders.substitute(&relative));
+            if path.exists() {
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Binary files are copied unchanged
+            match String::from_utf8(content) {
+                Ok(text) => fs::write(&path, placeholders.substitute(&text))?,
+                Err(e) => fs::write(&path, e.into_bytes())?,
+            }
+            created.push(path);
+        }
+
+        Ok(created)
+    }
+
+    fn files(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        match self {
+            Template::BuiltIn(name) => {
+                let built_in = BUILT_INS
+                    .iter()
+                    .find(|b| b.name == *name)
+                    .ok_or_else(|| PMSError::TemplateError(format!("Unknown built-in template: {}", name)))?;
+                Ok(built_in
+                    .files
+                    .iter()
+                    .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+                    .collect())
+            }
+            Template::Directory(dir) => template_files(dir)?
+                .into_iter()
+                .map(|path| {
+                    let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().to_string();
+                    Ok((relative, fs::read(&path)?))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Regular files of a template directory, leaving out its `.git`
+fn template_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|e| PMSError::TemplateError(e.to_string()))?;
+        if entry.file_type().is_some_and(|t| t.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TemplateInfo {
+    pub name: String,
+    /// Description for built-ins, directory for added templates
+    pub detail: String,
+    pub built_in: bool,
+}
+
+/// Templates added with `pms templates add`, one directory each.
+pub struct TemplateStore {
+    dir: PathBuf,
+}
+
+impl TemplateStore {
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(config::config_dir()?.join("templates")))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn list(&self) -> Result<Vec<TemplateInfo>> {
+        let mut templates: Vec<TemplateInfo> = BUILT_INS
+            .iter()
+            .map(|b| TemplateInfo {
+                name: b.name.to_string(),
+                detail: b.description.to_string(),
+                built_in: true,
+            })
+            .collect();
+
+        if self.dir.is_dir() {
+            let mut added = Vec::new();
+            for entry in fs::read_dir(&self.dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    added.push(TemplateInfo {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        detail: entry.path().display().to_string(),
+                        built_in: false,
+                    });
+                }
+            }
+            added.sort_by(|a, b| a.name.cmp(&b.name));
+            templates.extend(added);
+        }
+
+        Ok(templates)
+    }
+
+    /// Store a copy of a template directory or git repository under `name`.
+    pub fn add(&self, name: &str, source: &str) -> Result<()> {
+        validate_name(name)?;
+        if BUILT_INS.iter().any(|b| b.name == name) {
+            return Err(PMSError::TemplateError(format!("{} is a built-in template", name)).into());
+        }
+
+        let target = self.dir.join(name);
+        if target.exists() {
+            return Err(PMSError::TemplateError(format!("Template {} already exists", name)).into());
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let source_dir = Path::new(source);
+        if source_dir.is_dir() {
+            for file in template_files(source_dir)? {
+                let destination = target.join(file.strip_prefix(source_dir).unwrap_or(&file));
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&file, &destination)?;
+            }
+            // Keep empty templates listable
+            fs::create_dir_all(&target)?;
+        } else if is_git_url(source) {
+            let output = Command::new("git")
+                .args(["clone", "--depth", "1", "--quiet", source])
+                .arg(&target)
+                .output()?;
+            if !output.status.success() {
+                let _ = fs::remove_dir_all(&target);
+                return Err(PMSError::TemplateError(format!(
+                    "Failed to clone {}: {}",
+                    source,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )).into());
+            }
+            fs::remove_dir_all(target.join(".git"))?;
+        } else {
+            return Err(PMSError::TemplateError(format!(
+                "{} is neither a directory nor a git URL",
+                source
+            )).into());
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        if BUILT_INS.iter().any(|b| b.name == name) {
+            return Err(PMSError::TemplateError(format!("{} is a built-in template", name)).into());
+        }
+        validate_name(name)?;
+
+        let target = self.dir.join(name);
+        if !target.is_dir() {
+            return Err(PMSError::TemplateError(format!("Unknown template: {}", name)).into());
+        }
+        fs::remove_dir_all(target)?;
+        Ok(())
+    }
+
+    /// Find a template by name, falling back to a template directory path.
+    pub fn resolve(&self, name: &str) -> Result<Template> {
+        if let Some(built_in) = BUILT_INS.iter().find(|b| b.name == name) {
+            return Ok(Template::BuiltIn(built_in.name));
+        }
+        if validate_name(name).is_ok() && self.dir.join(name).is_dir() {
+            return Ok(Template::Directory(self.dir.join(name)));
+        }
+        if Path::new(name).is_dir() {
+            return Ok(Template::Directory(PathBuf::from(name)));
+        }
+
+        Err(PMSError::TemplateError(format!(
+            "Unknown template: {} (see pms templates list)",
+            name
+        )).into())
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        return Err(PMSError::TemplateError(format!(
+            "Invalid template name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        )).into());
+    }
+    Ok(())
+}
+
+fn is_git_url(source: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@", "file://"]
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn placeholders() -> Placeholders {
+        let mut config = Config::default();
+        config.git_username = "Ada".to_string();
+        config.git_email = "ada@example.com".to_string();
+        Placeholders::new("my-lib", &config)
+    }
+
+    #[test]
+    fn test_apply_built_in() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("README.md"), "existing").unwrap();
+
+        let created = Template::BuiltIn("rust-lib").apply(root, &placeholders()).unwrap();
+        assert!(created.contains(&root.join("Cargo.toml")));
+
+        let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"my_lib\""));
+        assert!(manifest.contains("authors = [\"Ada <ada@example.com>\"]"));
+        assert!(fs::read_to_string(root.join("LICENSE")).unwrap().contains(&Local::now().year().to_string()));
+
+        // Existing files are kept
+        assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "existing");
+
+        Template::BuiltIn("python").apply(root, &placeholders()).unwrap();
+        assert!(root.join("src/my_lib/__init__.py").exists());
+    }
+
+    #[test]
+    fn test_template_store() {
+        let temp_dir = tempdir().unwrap();
+        let store = TemplateStore::at(temp_dir.path().join("templates"));
+
+        let source = temp_dir.path().join("source");
+        fs::create_dir_all(source.join("docs")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("docs/{{project_name}}.md"), "# {{project_name}} by {{author}}").unwrap();
+
+        store.add("notes", source.to_str().unwrap()).unwrap();
+        assert!(store.add("notes", source.to_str().unwrap()).is_err());
+        assert!(store.add("basic", source.to_str().unwrap()).is_err());
+        assert!(store.add("../escape", source.to_str().unwrap()).is_err());
+
+        let names: Vec<String> = store.list().unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["basic", "rust-lib", "rust-bin", "python", "notes"]);
+
+        let target = temp_dir.path().join("project");
+        store.resolve("notes").unwrap().apply(&target, &placeholders()).unwrap();
+        assert_eq!(fs::read_to_string(target.join("docs/my-lib.md")).unwrap(), "# my-lib by Ada");
+        assert!(!target.join(".git").exists());
+
+        store.remove("notes").unwrap();
+        assert!(store.resolve("notes").is_err());
+        assert!(store.remove("basic").is_err());
+    }
+}